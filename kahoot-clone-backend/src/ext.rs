@@ -3,7 +3,7 @@ use axum::extract::ws::Message;
 use futures::{Stream, StreamExt};
 use serde::Serialize;
 
-use crate::ws::api::Action;
+use crate::ws::api::{Action, ProtocolError};
 
 pub trait ToMessageExt {
     fn to_message(&self) -> Message;
@@ -22,7 +22,10 @@ where
 
 #[async_trait]
 pub trait NextActionExt {
-    async fn next_action(&mut self) -> Option<Action>;
+    /// Waits for the next action, or the reason the next message isn't one.
+    ///
+    /// Returns `None` once the socket is closed.
+    async fn next_action(&mut self) -> Option<Result<Action, ProtocolError>>;
 }
 
 #[async_trait]
@@ -30,14 +33,12 @@ impl<S, E> NextActionExt for S
 where
     S: Stream<Item = Result<Message, E>> + Unpin + Send,
 {
-    async fn next_action(&mut self) -> Option<Action> {
+    async fn next_action(&mut self) -> Option<Result<Action, ProtocolError>> {
         loop {
-            let msg = self.next().await?.ok()?;
-
-            if let Ok(text) = msg.to_text() {
-                if let Ok(action) = serde_json::from_str(text) {
-                    return Some(action);
-                }
+            match self.next().await?.ok()? {
+                // Control frames aren't actions
+                Message::Ping(_) | Message::Pong(_) | Message::Close(_) => (),
+                msg => return Some(Action::try_from(msg)),
             }
         }
     }
@@ -44,6 +44,10 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 // You need to use a library for async in Rust because it doesn't provide
 // an "official" one out of the box which can be a pro or a con depending on
 // how you look at it.
+//
+// The note above is a doc comment followed by a blank line, which clippy
+// would otherwise complain about.
+#[allow(clippy::empty_line_after_outer_attr)]
 #[tokio::main]
 async fn main() {
     // Logging stuff, can ignore
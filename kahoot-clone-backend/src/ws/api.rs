@@ -22,16 +22,77 @@ use serde::{Deserialize, Serialize};
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum Action {
     // Initial message
-    CreateRoom { questions: Vec<Question> },
+    CreateRoom {
+        questions: Vec<Question>,
+        /// Optional room settings, defaults are used for anything missing.
+        #[serde(default)]
+        settings: RoomSettings,
+    },
     #[serde(rename_all = "camelCase")] // Renames fields as camelCase
-    JoinRoom { room_id: RoomId, username: String },
+    JoinRoom {
+        room_id: RoomId,
+        username: String,
+        /// The team the player would like to be on, in team games.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        team: Option<String>,
+    },
+    /// Puts a player who lost their connection back in the room, using the
+    /// token from `Joined`.
+    #[serde(rename_all = "camelCase")]
+    Rejoin {
+        room_id: RoomId,
+        resume_token: String,
+    },
 
     // Player only
-    Answer { choice: usize },
+    #[serde(rename_all = "camelCase")]
+    Answer {
+        /// The id of the round being answered, from `RoundBegin`.
+        round_id: u32,
+        // `flatten`:
+        // Inlines the fields of the submission into the action, so answers
+        // look like `{ "type": "answer", "roundId": 1, "choice": 1 }`.
+        #[serde(flatten)]
+        answer: Submission,
+    },
 
     // Host only
     BeginRound,
     EndRound,
+    /// Freezes the round timer, answers are rejected until `Resume`.
+    Pause,
+    Resume,
+    /// Skips the next question, only between rounds.
+    SkipQuestion,
+    /// Makes the question at `index` the next one, only between rounds.
+    ///
+    /// Questions which were already played are passed over as the game moves
+    /// on. Jumping to one replays it like `replayQuestion` with
+    /// `"overwrite"`.
+    JumpToQuestion { index: usize },
+    /// Plays the question at `index` again next, only between rounds.
+    ///
+    /// Afterwards the game carries on from where it was.
+    ReplayQuestion {
+        index: usize,
+        /// Defaults to `"overwrite"`.
+        #[serde(default)]
+        points: ReplayPoints,
+    },
+    /// Moves a player to another team, only while in the lobby.
+    AssignTeam { username: String, team: String },
+    /// Removes a player from the room, at any time.
+    KickPlayer {
+        username: String,
+        /// Also stops the name joining again. Defaults to `false`.
+        #[serde(default)]
+        ban: bool,
+    },
+    /// Lets a banned name join again.
+    Unban { username: String },
+    /// Stops new players joining until `UnlockRoom`.
+    LockRoom,
+    UnlockRoom,
 }
 
 /// Messages sent by the server to the room host.
@@ -43,26 +104,122 @@ pub enum HostEvent {
     RoomCreated {
         room_id: RoomId,
     },
+    /// Sent instead of `RoomCreated` if the quiz is invalid.
+    ///
+    /// The websocket connection will close after this message is sent.
+    CreateFailed {
+        reason: String,
+        /// Every problem found with the quiz.
+        problems: Vec<QuizProblem>,
+    },
 
     /// Sent whenever a user joins the room.
     UserJoined {
         username: String,
+        /// The team the user was put on, in team games.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        team: Option<String>,
+    },
+    /// Sent after the host moves a player to another team.
+    TeamAssigned {
+        username: String,
+        team: String,
+    },
+    /// Sent when a user loses their connection, if the room gives them time
+    /// to rejoin. They keep their place until `UserLeft` is sent.
+    UserDisconnected {
+        username: String,
+    },
+    /// Sent when a disconnected user comes back.
+    UserRejoined {
+        username: String,
     },
     /// Sent whenever a user leaves the room.
     UserLeft {
         username: String,
     },
+    /// Sent after the host kicks a player or bans a name.
+    ///
+    /// `UserLeft` follows straight away for kicked players.
+    PlayerKicked {
+        username: String,
+        banned: bool,
+    },
+    /// Sent after the host lifts a ban.
+    Unbanned {
+        username: String,
+    },
+    /// Sent after the host locks or unlocks the room.
+    LockChanged {
+        locked: bool,
+    },
     /// Sent whenever a user answers a question.
     ///
     /// Duplicate answers are automatically handled by the server, so the host
     /// does not need to deal with it.
     UserAnswered {
         username: String,
+        /// The raw text the user typed, for type answer questions.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        text: Option<String>,
+        /// The number of votes for each choice so far, for polls.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        choice_counts: Option<Vec<u32>>,
+    },
+
+    /// Sent whenever an entry is added to a word cloud.
+    ///
+    /// `count` is the new number of times the (normalized) entry was given.
+    WordCounted {
+        word: String,
+        count: u32,
     },
 
-    /// Sent when a new round begins.
+    /// Sent before a round begins if the room has a lead-in, while players
+    /// read the question.
+    #[serde(rename_all = "camelCase")]
+    GetReady {
+        question: String,
+        round_id: u32,
+        /// When answers open, in milliseconds since the unix epoch.
+        opens_at: u64,
+    },
+    /// Sent when a new round begins and answers open.
     RoundBegin {
         question: Question,
+        /// The position of the question in the quiz, starting from 0.
+        index: usize,
+        /// The number of questions in the quiz.
+        total: usize,
+        #[serde(flatten)]
+        timing: RoundTiming,
+    },
+    /// Sent after the host skips, jumps to or replays a question.
+    NextQuestion {
+        /// The question the next round will ask, or `null` if the game will
+        /// end instead.
+        index: Option<usize>,
+        total: usize,
+    },
+
+    /// Sent when the host pauses the round.
+    RoundPaused {
+        /// The seconds left in the round, frozen until it is resumed.
+        remaining: f64,
+    },
+    /// Sent when the host resumes a paused round.
+    RoundResumed {
+        /// The seconds left in the round.
+        remaining: f64,
+        /// When the round will now end, in milliseconds since the unix epoch.
+        deadline: u64,
+    },
+    /// Sent every so often during a round, if the room has ticks turned on.
+    #[serde(rename_all = "camelCase")]
+    Tick {
+        round_id: u32,
+        /// The seconds left in the round.
+        remaining: f64,
     },
     /// Sent when the round ends.
     ///
@@ -70,42 +227,409 @@ pub enum HostEvent {
     /// all players have answered, so the client does not need to deal with it.
     #[serde(rename_all = "camelCase")]
     RoundEnd {
-        /// The amount of points each player gains.
+        /// The amount of points each player gains, including streak bonuses.
         ///
         /// If they aren't in the object, they got the question wrong or
         /// didn't answer.
         point_gains: HashMap<String, u32>,
+        /// The number of players who picked each choice, for multiple choice,
+        /// multi-select and poll questions.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        choice_counts: Option<Vec<u32>>,
+        /// The correct answer, or `null` for polls and word clouds.
+        solution: Option<Solution>,
+        /// The number of times each entry was given, for word clouds.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        word_counts: Option<HashMap<String, u32>>,
+        /// Every player's current streak of correct answers.
+        streaks: HashMap<String, u32>,
+        /// Every team from first to last, in team games.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        teams: Option<Vec<TeamStanding>>,
+    },
+    /// Sent after `RoundEnd`, with the players who have the most points.
+    #[serde(rename_all = "camelCase")]
+    Leaderboard {
+        /// The best players, from first to last.
+        top: Vec<Standing>,
+        /// The number of players in the room.
+        player_count: usize,
     },
-    /// Sent if there are no more questions.
+    /// Sent if there are no more questions, with the final results.
     ///
     /// The websocket connection will close after this message is sent.
-    GameEnd,
+    #[serde(rename_all = "camelCase")]
+    GameEnd {
+        /// The players in the top three ranks.
+        podium: Vec<Standing>,
+        /// Every player, from first to last.
+        standings: Vec<Standing>,
+        /// The number of questions which had a correct answer, ie. not polls.
+        graded_questions: u32,
+        /// Every team from first to last, in team games.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        teams: Option<Vec<TeamStanding>>,
+    },
+
+    /// Sent when the host sends a message the server can't accept.
+    Error(ProtocolError),
 }
 
 /// Messages sent by the server to a player.
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum UserEvent {
-    /// Sent when the user successfully joins.
-    Joined,
-    /// Sent when the user couldn't join.
+    /// Sent when the user successfully joins or rejoins.
+    #[serde(rename_all = "camelCase")]
+    Joined {
+        /// Lets the user rejoin with `rejoin` if they lose their connection.
+        resume_token: String,
+    },
+    /// Sent when the user couldn't join, eg. because the room is locked.
     JoinFailed { reason: String },
+    /// Sent when the host removes the user from the room.
+    ///
+    /// The websocket connection will close after this message is sent.
+    Kicked {
+        /// Whether the user's name is banned from joining again.
+        banned: bool,
+    },
+    /// Sent when the user rejoins from another connection, which takes over
+    /// from this one.
+    ///
+    /// The websocket connection will close after this message is sent.
+    Replaced,
+    /// Sent after joining a team game, and whenever the host moves the user
+    /// to another team.
+    TeamAssigned { team: String },
+    /// Sent after joining or rejoining a game which has already started, so
+    /// the user can catch up with what's happening.
+    Snapshot {
+        #[serde(flatten)]
+        phase: GamePhase,
+        /// The user's total score so far.
+        score: u32,
+        /// The user's rank, or `null` if they haven't played a round yet.
+        rank: Option<usize>,
+    },
+
+    /// Sent before a round begins if the room has a lead-in, so the user can
+    /// read the question. Answers sent now are rejected.
+    #[serde(rename_all = "camelCase")]
+    GetReady {
+        question: String,
+        round_id: u32,
+        /// When answers open, in milliseconds since the unix epoch.
+        opens_at: u64,
+    },
+    /// Sent when the server records the user's answer.
+    AnswerAccepted,
+    /// Sent when the server doesn't count the user's answer.
+    AnswerRejected { reason: RejectReason },
 
-    /// Sent when a new round begins.
+    /// Sent when a new round begins and answers open.
     ///
-    /// The user is only sent information about how many choices there are.
+    /// The user is only sent what they need to answer, never the solution.
+    RoundBegin {
+        /// The question's text.
+        question: String,
+        #[serde(flatten)]
+        prompt: Prompt,
+        /// The question's media, if it is meant for players.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        media: Option<Media>,
+        #[serde(flatten)]
+        timing: RoundTiming,
+    },
+
+    /// Sent when the host pauses the round, answers are rejected until it
+    /// is resumed.
+    RoundPaused {
+        /// The seconds left in the round, frozen until it is resumed.
+        remaining: f64,
+    },
+    /// Sent when the host resumes a paused round.
+    RoundResumed {
+        /// The seconds left in the round.
+        remaining: f64,
+        /// When the round will now end, in milliseconds since the unix epoch.
+        deadline: u64,
+    },
+    /// Sent every so often during a round, if the room has ticks turned on.
     #[serde(rename_all = "camelCase")]
-    RoundBegin { choices: Vec<String> },
+    Tick {
+        round_id: u32,
+        /// The seconds left in the round.
+        remaining: f64,
+    },
 
     /// Sent when the round ends.
     ///
     /// The point gain field is a `number` if the player answered correctly,
-    /// otherwise it is `null`.
+    /// otherwise it is `null`. It includes any streak bonus.
+    #[serde(rename_all = "camelCase")]
+    RoundEnd {
+        point_gain: Option<u32>,
+        /// How the player's answer went.
+        outcome: AnswerOutcome,
+        /// The answer the player gave, or `null` if they didn't answer.
+        answer: Option<Submission>,
+        /// The correct answer, or `null` for polls and word clouds.
+        solution: Option<Solution>,
+        /// The player's total score so far.
+        score: u32,
+        /// The player's rank, starting from 1.
+        rank: usize,
+        /// How many points behind the next best player this player is, or
+        /// `null` if they are in first place.
+        gap_to_above: Option<u32>,
+        /// The number of questions the player has answered correctly in a row.
+        streak: u32,
+        /// Every team from first to last, in team games.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        teams: Option<Vec<TeamStanding>>,
+    },
+
+    /// Sent when the game is over, with the player's final results.
+    #[serde(rename_all = "camelCase")]
+    GameEnd {
+        score: u32,
+        rank: usize,
+        player_count: usize,
+        /// The number of questions the player answered completely correctly.
+        correct: u32,
+        /// The share of questions answered correctly from `0.0` to `1.0`, or
+        /// `null` if no question had a correct answer.
+        accuracy: Option<f64>,
+        /// Every team from first to last, in team games.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        teams: Option<Vec<TeamStanding>>,
+    },
+
+    /// Sent when the user sends a message the server can't accept.
+    Error(ProtocolError),
+}
+
+/// What a game which has started is doing.
+///
+/// Looks like `{ "phase": "round", ... }`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "phase", rename_all = "camelCase")]
+pub enum GamePhase {
+    /// Players are reading the next question, answers aren't open yet.
     #[serde(rename_all = "camelCase")]
-    RoundEnd { point_gain: Option<u32> },
+    GetReady {
+        question: String,
+        round_id: u32,
+        /// When answers open, in milliseconds since the unix epoch.
+        opens_at: u64,
+    },
+    /// A round is in progress, with the same fields as `RoundBegin`.
+    Round {
+        question: String,
+        #[serde(flatten)]
+        prompt: Prompt,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        media: Option<Media>,
+        #[serde(flatten)]
+        timing: RoundTiming,
+        /// The seconds left in the round.
+        remaining: f64,
+        /// Whether the host paused the round, freezing `remaining`.
+        paused: bool,
+        /// Whether the user already answered, eg. before reconnecting.
+        answered: bool,
+    },
+    /// The last round ended, and the host hasn't begun the next one.
+    BetweenRounds,
+}
+
+/// Why an answer wasn't counted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RejectReason {
+    /// No round is in progress.
+    NoRound,
+    /// The round is in its lead-in, answers aren't open yet.
+    NotOpen,
+    /// The round is paused.
+    Paused,
+    /// The user already answered this round.
+    AlreadyAnswered,
+    /// The answer is for a round other than the one in progress, eg. it was
+    /// sent just before the last round ended.
+    WrongRound,
+    /// The answer doesn't fit the question, eg. a choice out of range.
+    Invalid,
+}
+
+/// The server's clock for a round, so every screen shows the same countdown.
+///
+/// Times are in milliseconds since the unix epoch. Clients can compare
+/// `serverTime` with their own clock to correct for clock differences.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoundTiming {
+    /// Counts up from 1 for each round played.
+    pub round_id: u32,
+    /// When the server sent the event.
+    pub server_time: u64,
+    /// When the server stops accepting answers, unless the round is paused.
+    pub deadline: u64,
+}
+
+/// Describes why a message from a client was rejected.
+///
+/// Sent as `{ "type": "error", "code": "<code>", "message": "<details>" }`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtocolError {
+    pub code: ErrorCode,
+    /// A human readable explanation, not meant to be matched on.
+    pub message: String,
+}
+
+/// Machine readable protocol error codes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorCode {
+    /// The message isn't valid JSON.
+    MalformedJson,
+    /// The message's `"type"` isn't a known action.
+    UnknownAction,
+    /// The message is a known action, but its fields are wrong.
+    InvalidAction,
+    /// The action can't be sent by this client, or can't be sent right now.
+    UnexpectedAction,
+}
+
+/// Settings chosen by the host when creating a room.
+///
+/// Missing fields use their default values.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RoomSettings {
+    /// The number of players shown on leaderboards. Defaults to `5`.
+    pub leaderboard_size: usize,
+    /// The bonus points for each correct answer in a row after the first.
+    /// Defaults to `100`, and isn't given with `accuracyOnly` scoring.
+    pub streak_bonus: u32,
+    /// The largest bonus a streak can earn. Defaults to `500`.
+    pub max_streak_bonus: u32,
+    /// How answers are turned into points. Defaults to `"timeBased"`.
+    pub scoring: ScoringSettings,
+    /// Turns on team play. Defaults to `null`, where everyone plays alone.
+    pub teams: Option<TeamSettings>,
+    /// Sends a `tick` with the time left every this many seconds during
+    /// rounds. Defaults to `null`, where no ticks are sent.
+    pub tick_interval: Option<u32>,
+    /// The seconds players get to read each question before answers open.
+    /// Defaults to `0`, where answers open straight away.
+    pub lead_in: u16,
+    /// When players can join after the game has started. Defaults to
+    /// `"allowed"`.
+    pub late_join: LateJoin,
+    /// The seconds players who lose their connection keep their place and
+    /// score for, so they can rejoin. Defaults to `0`, which removes them
+    /// straight away.
+    pub reconnect_grace: u16,
+}
+
+/// When players can join a room whose game has already started.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LateJoin {
+    /// Players can join at any time, even mid-round.
+    #[default]
+    Allowed,
+    /// Players can only join before the game starts.
+    LobbyOnly,
+    /// Players can join before the game starts and between rounds.
+    BetweenRounds,
+}
+
+/// How players are split into teams, and how teams are scored.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamSettings {
+    /// The name of every team.
+    pub names: Vec<String>,
+    /// Defaults to `"balanced"`.
+    #[serde(default)]
+    pub assignment: TeamAssignment,
+    /// Defaults to `"sum"`.
+    #[serde(default)]
+    pub scoring: TeamScoring,
+}
 
-    /// Sent when the game is over.
-    GameEnd,
+/// How players joining the room are put on teams.
+///
+/// The host can move players to other teams in the lobby either way.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TeamAssignment {
+    /// Players join the team they ask for, or the smallest team if they
+    /// don't ask for one.
+    #[default]
+    Balanced,
+    /// Players are put on the smallest team, and the host decides the rest.
+    Host,
+}
+
+/// How a team's score is worked out from its members' scores.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TeamScoring {
+    /// The total of every member's score.
+    #[default]
+    Sum,
+    /// The average member score, so bigger teams have no advantage.
+    Average,
+    /// The score of the best member.
+    Best,
+}
+
+/// Picks a scoring strategy by name, along with its parameters.
+///
+/// Looks like `{ "name": "orderBased", "firstPoints": 1000 }`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "name", rename_all = "camelCase")]
+pub enum ScoringSettings {
+    /// Faster answers earn more points.
+    #[serde(rename_all = "camelCase")]
+    TimeBased { max_points: u32, min_points: u32 },
+    /// Earlier answers earn more points.
+    #[serde(rename_all = "camelCase")]
+    OrderBased { first_points: u32 },
+    /// Every correct answer earns the same points.
+    Flat { points: u32 },
+    /// Every completely correct answer earns a single point.
+    AccuracyOnly,
+}
+
+/// A player's place on a leaderboard.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Standing {
+    pub username: String,
+    /// The player's total score.
+    pub score: u32,
+    /// The number of questions the player answered completely correctly.
+    pub correct: u32,
+    /// The number of questions the player has answered correctly in a row.
+    pub streak: u32,
+    /// Starting from 1. Players with the same score share a rank.
+    pub rank: usize,
+}
+
+/// A team's place on a leaderboard.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TeamStanding {
+    pub team: String,
+    /// The team's score, worked out from its members' scores.
+    pub score: u32,
+    /// The number of players on the team.
+    pub members: usize,
+    /// Starting from 1. Teams with the same score share a rank.
+    pub rank: usize,
 }
 
 /// A type alias representing a room's id.
@@ -119,20 +643,490 @@ pub type RoomId = u32;
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Question {
     pub question: String,
-    /// All of the valid choices.
-    pub choices: Vec<String>,
-    /// The index of the correct answer.
-    pub answer: usize,
+    /// An image, video or audio clip shown with the question.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media: Option<Media>,
+    /// The kind of question, along with its choices and solution.
+    #[serde(flatten, deserialize_with = "kind_or_multiple_choice")]
+    pub kind: QuestionKind,
     /// The maximum number of seconds for this question.
     pub time: u16,
+    /// How many points this question is worth. Defaults to `"standard"`.
+    #[serde(default)]
+    pub points: PointMultiplier,
+}
+
+/// Multiplies the points a question is worth.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PointMultiplier {
+    #[default]
+    Standard,
+    Double,
+    /// The question is still graded, but earns nothing.
+    NoPoints,
+}
+
+/// What happens to the points from a question's earlier play when it is
+/// replayed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReplayPoints {
+    /// The replay's points replace the earlier ones.
+    #[default]
+    Overwrite,
+    /// The replay is only for practice, and earns nothing.
+    Ignore,
+}
+
+/// A problem found while checking a quiz.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuizProblem {
+    /// The index of the question with the problem, or `null` if the problem
+    /// is with the quiz as a whole.
+    pub question: Option<usize>,
+    /// The field with the problem, eg. `"choices[1].text"`.
+    pub field: String,
+    pub message: String,
+}
+
+/// The different kinds of questions, tagged by a `"kind"` field.
+///
+/// Questions without a `"kind"` field are treated as multiple choice.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum QuestionKind {
+    /// Exactly one of the choices is correct.
+    MultipleChoice {
+        /// All of the valid choices.
+        choices: Vec<Choice>,
+        /// The index of the correct answer.
+        answer: usize,
+    },
+    /// Any number of the choices are correct ("select all that apply").
+    MultiSelect {
+        /// All of the valid choices.
+        choices: Vec<Choice>,
+        /// The indices of every correct answer.
+        answers: Vec<usize>,
+        /// How selections which are only partially correct are scored.
+        #[serde(default)]
+        scoring: MultiSelectScoring,
+    },
+    /// Players type in their answer.
+    TypeAnswer {
+        /// Every answer that counts as correct.
+        accepted: Vec<String>,
+        /// How answers are cleaned up before being compared.
+        #[serde(default)]
+        normalization: Normalization,
+    },
+    /// Players estimate a number on a slider.
+    Slider {
+        /// The lowest value on the slider.
+        min: f64,
+        /// The highest value on the slider.
+        max: f64,
+        /// The distance between two neighbouring values on the slider.
+        step: f64,
+        /// The correct value.
+        answer: f64,
+        /// How far off an estimate can be before it earns nothing.
+        ///
+        /// Points shrink linearly with the distance from the answer, so an
+        /// estimate half of the tolerance away earns half of the points.
+        tolerance: f64,
+    },
+    /// Players put shuffled items back into the right order.
+    Ordering {
+        /// The items, in the correct order.
+        items: Vec<String>,
+        /// How orderings which are only partially correct are scored.
+        #[serde(default)]
+        scoring: OrderingScoring,
+    },
+    /// Players vote for a choice. There is no correct answer, and no points
+    /// are awarded.
+    Poll {
+        /// All of the valid choices.
+        choices: Vec<Choice>,
+    },
+    /// Players type in a few words, which are counted up into a word cloud.
+    /// No points are awarded.
+    #[serde(rename_all = "camelCase")]
+    WordCloud {
+        /// The longest entry allowed, in characters. Defaults to `25`.
+        #[serde(default = "default_max_length")]
+        max_length: usize,
+    },
+}
+
+/// Rules for comparing typed answers.
+///
+/// Missing fields use their default values.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Normalization {
+    /// Whether upper and lower case letters are the same. Defaults to `true`.
+    pub ignore_case: bool,
+    /// Whether leading, trailing and repeated whitespace is ignored.
+    /// Defaults to `true`.
+    pub ignore_whitespace: bool,
+    /// Whether accents are stripped, eg. "é" becomes "e". Defaults to `true`.
+    pub ignore_diacritics: bool,
+    /// The number of typos (inserted, deleted or replaced characters) that are
+    /// still accepted. Defaults to `0`.
+    pub max_typos: usize,
+}
+
+/// A choice of a multiple choice, multi-select or poll question.
+///
+/// Choices without media can also be sent as plain strings.
+//
+// `from`:
+// Deserializes a `RawChoice` first, then converts it into a `Choice`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "RawChoice")]
+pub struct Choice {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media: Option<Media>,
+}
+
+/// Either a plain string or a full choice object.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawChoice {
+    Text(String),
+    Choice {
+        text: String,
+        #[serde(default)]
+        media: Option<Media>,
+    },
+}
+
+/// Media attached to a question or a choice.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Media {
+    #[serde(flatten)]
+    pub source: MediaSource,
+    /// A description of the media for people who can't see or hear it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alt_text: Option<String>,
+    /// Whether players get the media on their own screens too, not only the
+    /// host. Defaults to `false`.
+    #[serde(default)]
+    pub for_players: bool,
+}
+
+/// Where media comes from, tagged by a `"kind"` field.
+///
+/// `start` and `end` are offsets in seconds for playing only part of a clip.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum MediaSource {
+    Image {
+        url: String,
+    },
+    Video {
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        start: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        end: Option<f64>,
+    },
+    Audio {
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        start: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        end: Option<f64>,
+    },
+}
+
+/// Scoring rules for multi-select questions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MultiSelectScoring {
+    /// Only the exact set of correct choices earns points.
+    #[default]
+    AllOrNothing,
+    /// Each correct choice earns a share of the points, and each wrong choice
+    /// takes a share away.
+    Partial,
+}
+
+/// Scoring rules for ordering questions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OrderingScoring {
+    /// Only the exact order earns points.
+    #[default]
+    ExactOrder,
+    /// Each item in the right position earns a share of the points.
+    PerPosition,
+}
+
+/// An answer sent by a player.
+///
+/// Which fields are present decides the variant, eg. `{ "choice": 1 }` or
+/// `{ "choices": [0, 2] }`.
+//
+// `untagged`:
+// Tries to deserialize each variant in order, picking the first that fits.
+//
+// Relevant: https://serde.rs/enum-representations.html#untagged
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Submission {
+    /// A single choice index.
+    Choice { choice: usize },
+    /// A set of choice indices.
+    Choices { choices: Vec<usize> },
+    /// A typed in answer.
+    Text { text: String },
+    /// A number picked on a slider.
+    Number { value: f64 },
+    /// An ordering of the shown items, as indices into the shown list from
+    /// first to last.
+    Order { order: Vec<usize> },
+}
+
+/// The correct answer to a question, revealed when the round ends.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Solution {
+    /// The index of the correct choice.
+    Choice { choice: usize },
+    /// The indices of every correct choice.
+    Choices { choices: Vec<usize> },
+    /// Every accepted answer.
+    Accepted { accepted: Vec<String> },
+    /// The correct number.
+    Number { value: f64 },
+    /// The items in the correct order.
+    Items { items: Vec<String> },
+}
+
+/// How a player's answer went in a round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AnswerOutcome {
+    Correct,
+    /// Earned some credit, but not all of it.
+    PartlyCorrect,
+    Wrong,
+    /// Answered a poll or a word cloud, which have no correct answer.
+    Answered,
+    NoAnswer,
+}
+
+/// The part of a question which is shown to players.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Prompt {
+    MultipleChoice { choices: Vec<Choice> },
+    MultiSelect { choices: Vec<Choice> },
+    TypeAnswer,
+    Slider { min: f64, max: f64, step: f64 },
+    /// The items are shuffled.
+    Ordering { items: Vec<String> },
+    Poll { choices: Vec<Choice> },
+    #[serde(rename_all = "camelCase")]
+    WordCloud { max_length: usize },
+}
+
+impl From<RawChoice> for Choice {
+    fn from(raw: RawChoice) -> Self {
+        match raw {
+            RawChoice::Text(text) => Choice { text, media: None },
+            RawChoice::Choice { text, media } => Choice { text, media },
+        }
+    }
+}
+
+impl From<&str> for Choice {
+    fn from(text: &str) -> Self {
+        Choice {
+            text: String::from(text),
+            media: None,
+        }
+    }
+}
+
+impl Default for RoomSettings {
+    fn default() -> Self {
+        Self {
+            leaderboard_size: 5,
+            streak_bonus: 100,
+            max_streak_bonus: 500,
+            scoring: ScoringSettings::default(),
+            teams: None,
+            tick_interval: None,
+            lead_in: 0,
+            late_join: LateJoin::default(),
+            reconnect_grace: 0,
+        }
+    }
+}
+
+impl Default for ScoringSettings {
+    fn default() -> Self {
+        Self::TimeBased {
+            max_points: 1000,
+            min_points: 500,
+        }
+    }
+}
+
+impl Default for Normalization {
+    fn default() -> Self {
+        Self {
+            ignore_case: true,
+            ignore_whitespace: true,
+            ignore_diacritics: true,
+            max_typos: 0,
+        }
+    }
+}
+
+fn default_max_length() -> usize {
+    25
+}
+
+/// Deserializes a flattened `QuestionKind`, defaulting to multiple choice
+/// when there is no `"kind"` field so older clients keep working.
+fn kind_or_multiple_choice<'de, D>(deserializer: D) -> Result<QuestionKind, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::Error;
+
+    let mut fields = serde_json::Map::deserialize(deserializer)?;
+    fields
+        .entry("kind")
+        .or_insert_with(|| "multipleChoice".into());
+
+    QuestionKind::deserialize(serde_json::Value::Object(fields)).map_err(D::Error::custom)
 }
 
 // Trait implementation stuff. Doesn't matter too much.
 impl TryFrom<Message> for Action {
-    type Error = ();
+    type Error = ProtocolError;
 
     fn try_from(msg: Message) -> Result<Action, Self::Error> {
-        let text = msg.to_text().map_err(|_| ())?;
-        serde_json::from_str(text).map_err(|_| ())
+        let text = msg
+            .to_text()
+            .map_err(|_| ProtocolError::new(ErrorCode::MalformedJson, "Message is not text"))?;
+
+        let value: serde_json::Value = serde_json::from_str(text)
+            .map_err(|err| ProtocolError::new(ErrorCode::MalformedJson, err.to_string()))?;
+
+        // Only the action's own tag decides whether it is known, as unknown
+        // variants nested in its fields are reported the same way by `serde`
+        if let Some(tag) = value.get("type").and_then(|tag| tag.as_str()) {
+            if !ACTION_TYPES.contains(&tag) {
+                return Err(ProtocolError::new(ErrorCode::UnknownAction, format!("unknown action `{tag}`")));
+            }
+        }
+
+        serde_json::from_value(value).map_err(|err| ProtocolError::new(ErrorCode::InvalidAction, err.to_string()))
+    }
+}
+
+/// The `"type"` of every action. The `action_types` test checks it matches
+/// `Action`.
+const ACTION_TYPES: &[&str] = &[
+    "createRoom",
+    "joinRoom",
+    "rejoin",
+    "answer",
+    "beginRound",
+    "endRound",
+    "pause",
+    "resume",
+    "skipQuestion",
+    "jumpToQuestion",
+    "replayQuestion",
+    "assignTeam",
+    "kickPlayer",
+    "unban",
+    "lockRoom",
+    "unlockRoom",
+];
+
+impl ProtocolError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// An error for an action sent by the wrong role or at the wrong time.
+    pub fn unexpected(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::UnexpectedAction, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{Action, ReplayPoints, RoomSettings, Submission, ACTION_TYPES};
+
+    #[test]
+    fn action_types() {
+        let name = || String::from("Alice");
+        let actions = [
+            Action::CreateRoom { questions: Vec::new(), settings: RoomSettings::default() },
+            Action::JoinRoom { room_id: 1, username: name(), team: None },
+            Action::Rejoin { room_id: 1, resume_token: String::new() },
+            Action::Answer { round_id: 1, answer: Submission::Choice { choice: 0 } },
+            Action::BeginRound,
+            Action::EndRound,
+            Action::Pause,
+            Action::Resume,
+            Action::SkipQuestion,
+            Action::JumpToQuestion { index: 0 },
+            Action::ReplayQuestion { index: 0, points: ReplayPoints::default() },
+            Action::AssignTeam { username: name(), team: String::from("Red") },
+            Action::KickPlayer { username: name(), ban: false },
+            Action::Unban { username: name() },
+            Action::LockRoom,
+            Action::UnlockRoom,
+        ];
+
+        for action in &actions {
+            // A new action won't compile here until it's added above, and
+            // then the check below makes sure it's in `ACTION_TYPES` too
+            match action {
+                Action::CreateRoom { .. }
+                | Action::JoinRoom { .. }
+                | Action::Rejoin { .. }
+                | Action::Answer { .. }
+                | Action::BeginRound
+                | Action::EndRound
+                | Action::Pause
+                | Action::Resume
+                | Action::SkipQuestion
+                | Action::JumpToQuestion { .. }
+                | Action::ReplayQuestion { .. }
+                | Action::AssignTeam { .. }
+                | Action::KickPlayer { .. }
+                | Action::Unban { .. }
+                | Action::LockRoom
+                | Action::UnlockRoom => {}
+            }
+        }
+
+        let types: HashSet<String> = actions
+            .iter()
+            .map(|action| serde_json::to_value(action).unwrap()["type"].as_str().unwrap().to_string())
+            .collect();
+        let listed: HashSet<String> = ACTION_TYPES.iter().map(|tag| tag.to_string()).collect();
+        assert_eq!(types, listed);
+        assert_eq!(listed.len(), ACTION_TYPES.len());
     }
 }
@@ -13,15 +13,35 @@ pub mod api;
 /// Contains data for representing game states.
 pub mod state;
 
-use api::{Action, HostEvent, Question, RoomId, UserEvent};
+/// Contains logic for grading answers and presenting questions to players.
+pub mod question;
 
-use state::{GameEvent, PlayerAnswer, Room, SharedState, Users};
+/// Contains checks for quizzes sent by hosts.
+pub mod validate;
+
+/// Contains score keeping over a whole game.
+pub mod scores;
+
+/// Contains the strategies for turning answers into points.
+pub mod scoring;
+
+use api::{
+    Action, AnswerOutcome, ErrorCode, GamePhase, HostEvent, ProtocolError, Question, RejectReason, ReplayPoints, RoomId,
+    RoomSettings, RoundTiming, Submission, UserEvent,
+};
+
+use state::{AssignError, GameEvent, JoinError, JoinedUser, Phase, PlayerAnswer, Progress, Room, SharedState, Users};
+
+use question::{cloud_word, tally, AskedQuestion};
+
+use scores::{find_standing, rank_teams, Scoreboard};
+use scoring::ScoredAnswer;
 
 use crate::ext::{ToMessageExt, NextActionExt};
 
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use axum::extract::ws::{WebSocket, Message};
 use axum::extract::WebSocketUpgrade;
@@ -30,6 +50,7 @@ use axum::routing::get;
 use axum::{Extension, Router};
 
 use tokio::sync::{mpsc, watch};
+use tokio::time::MissedTickBehavior;
 
 use futures::{SinkExt, StreamExt};
 
@@ -69,35 +90,76 @@ async fn handle_ws_connection(
 
 /// Deals with an upgraded websocket.
 async fn handle_ws(mut socket: WebSocket, state: SharedState) {
-    let action = if let Some(action) = socket.next_action().await {
-        action
-    } else {
-        tracing::error!("Couldn't parse initial action");
-        return;
+    let action = match socket.next_action().await {
+        Some(Ok(action)) => action,
+        Some(Err(error)) => {
+            tracing::error!("Couldn't parse initial action: {}", error.message);
+            // Host and user errors look the same, so it doesn't matter which
+            // is sent here
+            let _ = socket.send(UserEvent::Error(error).to_message()).await;
+            return;
+        }
+        None => return,
     };
 
     match action {
-        Action::CreateRoom { questions } => create_room(socket, state, questions).await,
-        Action::JoinRoom { room_id, username } => join_room(socket, state, room_id, username).await,
-        action => tracing::error!("Invalid first action {action:?}"),
+        Action::CreateRoom { questions, settings } => {
+            create_room(socket, state, questions, settings).await
+        }
+        Action::JoinRoom { room_id, username, team } => {
+            join_room(socket, state, room_id, JoinRequest::New { username, team }).await
+        }
+        Action::Rejoin { room_id, resume_token } => {
+            join_room(socket, state, room_id, JoinRequest::Resume { resume_token }).await
+        }
+        action => {
+            tracing::error!("Invalid first action {action:?}");
+            let error = ProtocolError::unexpected("The first action must be `createRoom`, `joinRoom` or `rejoin`");
+            let _ = socket.send(UserEvent::Error(error).to_message()).await;
+        }
     };
 }
 
 /// Handles room creation.
 ///
 /// The websocket will be treated as the "host" from now on.
-async fn create_room(mut host: WebSocket, state: SharedState, questions: Vec<Question>) {
+async fn create_room(
+    mut host: WebSocket,
+    state: SharedState,
+    questions: Vec<Question>,
+    settings: RoomSettings,
+) {
+    // Refuse to create rooms for invalid quizzes or settings
+    let problems: Vec<_> = [validate::check_quiz(&questions), validate::check_settings(&settings)]
+        .into_iter()
+        .filter_map(Result::err)
+        .flatten()
+        .collect();
+    if !problems.is_empty() {
+        tracing::error!("Invalid quiz with {} problem(s)", problems.len());
+        let event = HostEvent::CreateFailed {
+            reason: String::from("Invalid quiz"),
+            problems,
+        };
+        let _ = host.send(event.to_message()).await;
+        return;
+    }
+
     tracing::debug!("Creating room...");
 
     let (action_tx, mut action_rx) = mpsc::channel(20);
     let (result_tx, result_rx) = watch::channel(GameEvent::InLobby);
-    let (users, mut player_event_rx) = Users::new();
+    let grace = Duration::from_secs(settings.reconnect_grace as u64);
+    let (users, mut player_event_rx) = Users::new(settings.teams.clone(), grace);
 
     // Create an empty room
     let room = Room {
         users,
         result_stream: result_rx,
         action_stream: action_tx,
+        late_join: settings.late_join,
+        phase: Mutex::new(Phase::Lobby),
+        standings: Mutex::new(Arc::new(Vec::new())),
     };
 
     // Put the room into an `Arc`
@@ -138,14 +200,23 @@ async fn create_room(mut host: WebSocket, state: SharedState, questions: Vec<Que
         let host_tx = host_tx.clone();
         tokio::spawn(async move {
             while let Some(event) = player_event_rx.recv().await {
-                let event = match event {
-                    state::PlayerEvent::Joined(username) => HostEvent::UserJoined { username },
-                    state::PlayerEvent::Left(username) => HostEvent::UserLeft { username },
+                let events = match event {
+                    state::PlayerEvent::Joined { username, team } => vec![HostEvent::UserJoined { username, team }],
+                    state::PlayerEvent::Disconnected(username) => vec![HostEvent::UserDisconnected { username }],
+                    state::PlayerEvent::Rejoined(username) => vec![HostEvent::UserRejoined { username }],
+                    state::PlayerEvent::Left(username) => vec![HostEvent::UserLeft { username }],
+                    // Kicked players leave straight away
+                    state::PlayerEvent::Kicked { username, banned } => vec![
+                        HostEvent::PlayerKicked { username: username.clone(), banned },
+                        HostEvent::UserLeft { username },
+                    ],
                 };
 
                 // Stop if receiver is closed
-                if host_tx.send(event.to_message()).await.is_err() {
-                    break;
+                for event in events {
+                    if host_tx.send(event.to_message()).await.is_err() {
+                        return;
+                    }
                 }
             }
         });
@@ -162,13 +233,53 @@ async fn create_room(mut host: WebSocket, state: SharedState, questions: Vec<Que
         })
     };
 
+    let mut progress = Progress::new(questions.len());
+
     // Wait until host begins room and there is at least one player in lobby
     loop {
-        match host_rx.next_action().await {
+        let act = tokio::select! {
+            act = host_rx.next_action() => act,
+            Some(PlayerAnswer { username, .. }) = action_rx.recv() => {
+                reject_answer(&room, &username, RejectReason::NoRound);
+                continue;
+            }
+        };
+
+        match act {
             // If action is begin round and there is at least one player
-            Some(Action::BeginRound) if room.users.player_count() > 0 => break,
-            // If received action but does not match above, ignore
-            Some(_) => (),
+            Some(Ok(Action::BeginRound)) if room.users.player_count() > 0 => break,
+            Some(Ok(Action::BeginRound)) => {
+                let error = ProtocolError::unexpected("The room has no players yet");
+                report_error(&host_tx, error).await;
+            }
+            // Move a player to another team
+            Some(Ok(Action::AssignTeam { username, team })) => {
+                let message = match room.users.assign_team(&username, &team) {
+                    Ok(()) => {
+                        let event = HostEvent::TeamAssigned { username, team };
+                        let _ = host_tx.send(event.to_message()).await;
+                        continue;
+                    }
+                    Err(AssignError::NoTeams) => "This isn't a team game",
+                    Err(AssignError::UnknownTeam) => "There is no team with that name",
+                    Err(AssignError::UnknownUser) => "There is no player with that name",
+                };
+                report_error(&host_tx, ProtocolError::new(ErrorCode::InvalidAction, message)).await;
+            }
+            // Choose where the game starts
+            Some(Ok(action @ (Action::SkipQuestion | Action::JumpToQuestion { .. } | Action::ReplayQuestion { .. }))) => {
+                navigate(&mut progress, action, &host_tx).await;
+            }
+            // Kick, ban and lock
+            Some(Ok(action @ (Action::KickPlayer { .. } | Action::Unban { .. } | Action::LockRoom | Action::UnlockRoom))) => {
+                moderate(&room, action, &host_tx).await;
+            }
+            // If received action but does not match above, tell the host
+            Some(Ok(_)) => {
+                let error = ProtocolError::unexpected("The game hasn't started yet");
+                report_error(&host_tx, error).await;
+            }
+            Some(Err(error)) => report_error(&host_tx, error).await,
 
             // If host dc's, close room
             None => {
@@ -181,30 +292,135 @@ async fn create_room(mut host: WebSocket, state: SharedState, questions: Vec<Que
 
     tracing::debug!("Starting game...");
 
-    for question in questions.into_iter() {
+    let mut scoreboard = Scoreboard::new(&settings);
+    let scoring = settings.scoring.strategy();
+    let mut round_id = 0;
+
+    while let Some((index, replay)) = progress.start_round() {
+        let question = &questions[index];
         let mut point_gains = HashMap::new();
-        let mut answered = HashSet::new();
-        let mut points = 1000;
+        // What each player answered, and how it went
+        let mut answers = HashMap::new();
+        let mut correct = HashSet::new();
 
         // Save values
-        let question_time = question.time as u64;
-        let choices = question.choices.clone();
-        let answer = question.answer;
+        let question_time = Duration::from_secs(question.time as u64);
+        let asked = AskedQuestion::new(question.kind.clone());
+        let prompt = asked.prompt();
+        let media = question.media.clone().filter(|media| media.for_players);
+
+        // Questions with choices count how often each was picked
+        let mut choice_counts = asked.choice_count().map(|choices| vec![0; choices]);
+
+        // Word clouds count how often each entry was given
+        let mut word_counts = asked.is_word_cloud().then(HashMap::new);
+
+        // Answers still in the stream from earlier rounds are rejected
+        // during this one, as their round id doesn't match
+        round_id += 1;
+
+        // Give everyone time to read the question before answers open
+        if settings.lead_in > 0 {
+            let lead_in = Duration::from_secs(settings.lead_in as u64);
+            let opens_at = unix_millis(SystemTime::now() + lead_in);
+            let text = question.question.clone();
+
+            tracing::debug!("Alerting everyone to get ready...");
+            let _ = host_tx
+                .send(HostEvent::GetReady { question: text.clone(), round_id, opens_at }.to_message())
+                .await;
+            room.set_phase(Phase::GetReady { question: text.clone(), round_id, opens_at });
+            let _ = result_tx.send(GameEvent::GetReady { question: text, round_id, opens_at });
+
+            let lead_in_task = tokio::time::sleep(lead_in);
+            tokio::pin!(lead_in_task);
+            loop {
+                tokio::select! {
+                    _ = (&mut lead_in_task) => break,
+
+                    act = host_rx.next_action() => {
+                        match act {
+                            Some(Ok(action @ (Action::KickPlayer { .. } | Action::Unban { .. } | Action::LockRoom | Action::UnlockRoom))) => {
+                                moderate(&room, action, &host_tx).await;
+                            }
+                            Some(Ok(_)) => {
+                                let error = ProtocolError::unexpected("The round hasn't opened yet");
+                                report_error(&host_tx, error).await;
+                            }
+                            Some(Err(error)) => report_error(&host_tx, error).await,
+                            None => {
+                                tracing::debug!("Host disconnected...");
+                                state.remove_room(&room_id).await;
+                                return;
+                            }
+                        }
+                    }
+
+                    // Tell players who answer too early why it didn't count
+                    Some(PlayerAnswer { username, round_id: answered_round, .. }) = action_rx.recv() => {
+                        let reason = if answered_round == round_id {
+                            RejectReason::NotOpen
+                        } else {
+                            RejectReason::WrongRound
+                        };
+                        reject_answer(&room, &username, reason);
+                    }
+                }
+            }
+        }
+
+        // Answers are timed from here, and the deadline sent to clients is
+        // the same one the server uses
+        let mut round_start = Instant::now();
+        let server_time = SystemTime::now();
+        let timing = RoundTiming {
+            round_id,
+            server_time: unix_millis(server_time),
+            deadline: unix_millis(server_time + question_time),
+        };
+        let time_task = tokio::time::sleep(question_time);
+        tokio::pin!(time_task);
+
+        // Sends the time left every so often, if the room wants it
+        let mut ticker = {
+            let period = Duration::from_secs(settings.tick_interval.unwrap_or(1).max(1) as u64);
+            tokio::time::interval_at(tokio::time::Instant::now() + period, period)
+        };
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
         // Alert host that the round began
         tracing::debug!("Alerting host that round began...");
-        let _ = host_tx.send(HostEvent::RoundBegin { question }.to_message()).await;
+        let _ = host_tx
+            .send(HostEvent::RoundBegin {
+                question: question.clone(),
+                index,
+                total: progress.total(),
+                timing,
+            }.to_message())
+            .await;
 
         // Alert players a round began
         tracing::debug!("Alerting players that round began...");
-        let _ = result_tx.send(GameEvent::RoundBegin { choices });
+        room.set_phase(Phase::Round {
+            question: question.question.clone(),
+            prompt: prompt.clone(),
+            media: media.clone(),
+            round_id,
+            ends: round_start + question_time,
+            paused: None,
+            answered: HashSet::new(),
+        });
+        let _ = result_tx.send(GameEvent::RoundBegin {
+            question: question.question.clone(),
+            prompt,
+            media,
+            timing,
+        });
 
-        // Keep taking from stream until it is empty
-        while let Ok(_) = action_rx.try_recv() { }
+        // When the round was paused and how much time was left, if it is
+        let mut paused: Option<(Instant, Duration)> = None;
 
         // Wait for round end event
-        let time_task = tokio::time::sleep(Duration::from_secs(question_time));
-        tokio::pin!(time_task);
         loop {
             // Pick whichever future resolves first
             tokio::select! {
@@ -212,12 +428,57 @@ async fn create_room(mut host: WebSocket, state: SharedState, questions: Vec<Que
                 act = host_rx.next_action() => {
                     match act {
                         // If the host sent an end round action, exit loop
-                        Some(Action::EndRound) => {
+                        Some(Ok(Action::EndRound)) => {
                             tracing::debug!("Host forcefully ended round");
                             break;
                         }
-                        // Ignore all other actions
-                        Some(_) => (),
+                        // Freeze the timer
+                        Some(Ok(Action::Pause)) if paused.is_none() => {
+                            let remaining = time_task
+                                .deadline()
+                                .saturating_duration_since(tokio::time::Instant::now());
+                            paused = Some((Instant::now(), remaining));
+                            room.set_round_clock(remaining, true);
+
+                            tracing::debug!("Host paused round");
+                            let remaining = remaining.as_secs_f64();
+                            let _ = host_tx.send(HostEvent::RoundPaused { remaining }.to_message()).await;
+                            let _ = result_tx.send(GameEvent::Paused(remaining));
+                        }
+                        // Start the timer again with the time that was left
+                        Some(Ok(Action::Resume)) if paused.is_some() => {
+                            let (paused_at, remaining) = paused.take().unwrap();
+                            time_task.as_mut().reset(tokio::time::Instant::now() + remaining);
+                            room.set_round_clock(remaining, false);
+
+                            // Time spent paused doesn't count against answers
+                            round_start += paused_at.elapsed();
+                            ticker.reset();
+
+                            tracing::debug!("Host resumed round");
+                            let deadline = unix_millis(SystemTime::now() + remaining);
+                            let remaining = remaining.as_secs_f64();
+                            let _ = host_tx.send(HostEvent::RoundResumed { remaining, deadline }.to_message()).await;
+                            let _ = result_tx.send(GameEvent::Resumed { remaining, deadline });
+                        }
+                        Some(Ok(Action::Pause)) => {
+                            let error = ProtocolError::unexpected("The round is already paused");
+                            report_error(&host_tx, error).await;
+                        }
+                        Some(Ok(Action::Resume)) => {
+                            let error = ProtocolError::unexpected("The round isn't paused");
+                            report_error(&host_tx, error).await;
+                        }
+                        // Players can be removed mid-round
+                        Some(Ok(action @ (Action::KickPlayer { .. } | Action::Unban { .. } | Action::LockRoom | Action::UnlockRoom))) => {
+                            moderate(&room, action, &host_tx).await;
+                        }
+                        // Tell the host about all other actions
+                        Some(Ok(_)) => {
+                            let error = ProtocolError::unexpected("A round is in progress");
+                            report_error(&host_tx, error).await;
+                        }
+                        Some(Err(error)) => report_error(&host_tx, error).await,
 
                         // If the action is none, the socket must have dc'd
                         None => {
@@ -228,46 +489,122 @@ async fn create_room(mut host: WebSocket, state: SharedState, questions: Vec<Que
                     }
                 }
 
-                // Timeout
-                _ = (&mut time_task) => {
+                // Timeout, which can't happen while paused
+                _ = (&mut time_task), if paused.is_none() => {
                     tracing::debug!("Question timeout");
                     break;
                 }
 
+                // Time left, which isn't sent while paused
+                _ = ticker.tick(), if paused.is_none() && settings.tick_interval.is_some() => {
+                    let remaining = time_task
+                        .deadline()
+                        .saturating_duration_since(tokio::time::Instant::now())
+                        .as_secs_f64();
+                    let _ = host_tx.send(HostEvent::Tick { round_id, remaining }.to_message()).await;
+                    let _ = result_tx.send(GameEvent::Tick { round_id, remaining });
+                }
+
                 // User answers
-                Some(PlayerAnswer { username, choice }) = action_rx.recv() => {
-                    if answered.contains(&username) {
+                Some(PlayerAnswer { username, round_id: answered_round, answer, received }) = action_rx.recv() => {
+                    // Answers meant for another round never count
+                    if answered_round != round_id {
+                        reject_answer(&room, &username, RejectReason::WrongRound);
+                        continue;
+                    }
+
+                    if answers.contains_key(&username) {
+                        reject_answer(&room, &username, RejectReason::AlreadyAnswered);
+                        continue;
+                    }
+
+                    // Nobody can answer while the round is paused
+                    if paused.is_some() {
+                        reject_answer(&room, &username, RejectReason::Paused);
                         continue;
                     }
 
-                    answered.insert(username.clone());
+                    // Reject answers that don't fit the question
+                    let credit = if let Some(credit) = asked.grade(&answer) {
+                        credit
+                    } else {
+                        tracing::debug!("`{username}` sent an invalid answer {answer:?}");
+                        reject_answer(&room, &username, RejectReason::Invalid);
+                        continue;
+                    };
+
+                    // Let the player know their answer counts
+                    room.users.send_to(&username, UserEvent::AnswerAccepted);
+                    room.mark_answered(&username);
 
-                    // Tell host user answered
+                    answers.insert(username.clone(), (answer.clone(), asked.outcome(credit)));
+
+                    // Count the picked choices
+                    if let Some(counts) = &mut choice_counts {
+                        tally(counts, &answer);
+                    }
+
+                    // Tell host user answered, along with what they typed
+                    let text = match &answer {
+                        Submission::Text { text } => Some(text.clone()),
+                        _ => None,
+                    };
                     let _ = host_tx.send(HostEvent::UserAnswered {
-                            username: username.clone()
+                            username: username.clone(),
+                            text,
+                            // Only polls show live votes
+                            choice_counts: asked.poll_choices().and(choice_counts.clone()),
                         }.to_message())
                         .await;
 
-                    tracing::debug!("`{username}` answered {choice}");
+                    // Add to the word cloud
+                    if let (Some(counts), Submission::Text { text }) = (&mut word_counts, &answer) {
+                        let word = cloud_word(text);
+                        let count = counts.entry(word.clone()).or_insert(0);
+                        *count += 1;
 
-                    // If the choice is correct
-                    if choice == answer {
-                        // Update points log
-                        tracing::debug!("`{username}` +{points}");
-                        point_gains.insert(username, points);
+                        let event = HostEvent::WordCounted { word, count: *count };
+                        let _ = host_tx.send(event.to_message()).await;
+                    }
+
+                    tracing::debug!("`{username}` answered {answer:?}");
+
+                    if asked.is_graded() && credit >= 1.0 {
+                        correct.insert(username.clone());
+                    }
+
+                    // Let the room's scoring strategy decide what answers with
+                    // some credit are worth
+                    let gain = if credit > 0.0 {
+                        let points = scoring.points(&ScoredAnswer {
+                            question,
+                            submission: &answer,
+                            credit,
+                            latency: received.saturating_duration_since(round_start),
+                            order: point_gains.len(),
+                            history: scoreboard.record(&username),
+                        });
+                        question.points.apply(points)
+                    } else {
+                        0
+                    };
 
-                        // Decrease next point gain
-                        points = (points * 10 / 11).max(1);
+                    // If the answer earned any points
+                    if gain > 0 {
+                        // Update points log
+                        tracing::debug!("`{username}` +{gain}");
+                        point_gains.insert(username, gain);
                     }
 
-                    // Has every player answered
+                    // Has every connected player answered
                     let all_answered = room
                         .users
                         .users
                         .lock()
                         .unwrap()
                         .iter()
-                        .all(|name| answered.contains(name));
+                        .filter(|(_, member)| member.connected)
+                        .all(|(name, _)| answers.contains_key(name));
 
                     // If everyone has answered, leave loop
                     if all_answered {
@@ -276,32 +613,88 @@ async fn create_room(mut host: WebSocket, state: SharedState, questions: Vec<Que
                 }
             };
         }
-        drop(time_task);
 
         tracing::debug!("End of round...");
 
+        // Add streak bonuses and rank everyone still in the room
+        let players = room.users.usernames();
+        if replay == Some(ReplayPoints::Ignore) {
+            // Practice replays don't change anyone's results
+            point_gains.clear();
+        } else {
+            scoreboard.add_round(index, &players, &mut point_gains, &correct, asked.is_graded());
+        }
+        let ranking = scoreboard.rank(&players);
+        let teams = room
+            .users
+            .team_members()
+            .map(|(teams, members)| rank_teams(&ranking, teams, &members));
+
         // Tell host that the round ended
         tracing::debug!("Alerting host that round ended...");
         let _ = host_tx
             .send(HostEvent::RoundEnd {
                 point_gains: point_gains.clone(),
+                choice_counts,
+                solution: asked.solution(),
+                word_counts,
+                streaks: ranking
+                    .iter()
+                    .map(|standing| (standing.username.clone(), standing.streak))
+                    .collect(),
+                teams: teams.clone(),
+            }.to_message())
+            .await;
+
+        // Show the host the best players
+        let _ = host_tx
+            .send(HostEvent::Leaderboard {
+                top: ranking.iter().take(settings.leaderboard_size).cloned().collect(),
+                player_count: ranking.len(),
             }.to_message())
             .await;
 
         // Alert players round ended
         tracing::debug!("Alerting players that round ended...");
+        let ranking = Arc::new(ranking);
+        room.set_standings(Arc::clone(&ranking));
+        room.set_phase(Phase::BetweenRounds);
         let _ = result_tx.send(GameEvent::RoundEnd {
+            answers: Arc::new(answers),
+            solution: asked.solution(),
             point_gains: Arc::new(point_gains),
+            ranking,
+            teams: teams.map(Arc::new),
         });
 
         // Wait until host begins next round
         loop {
-            match host_rx.next_action().await {
+            let act = tokio::select! {
+                act = host_rx.next_action() => act,
+                Some(PlayerAnswer { username, .. }) = action_rx.recv() => {
+                    reject_answer(&room, &username, RejectReason::NoRound);
+                    continue;
+                }
+            };
+
+            match act {
                 // If action is begin round, break loop
-                Some(Action::BeginRound) => break,
-                
-                // If host sends irrelevant message, ignore
-                Some(_) => (),
+                Some(Ok(Action::BeginRound)) => break,
+
+                // Choose the next question
+                Some(Ok(action @ (Action::SkipQuestion | Action::JumpToQuestion { .. } | Action::ReplayQuestion { .. }))) => {
+                    navigate(&mut progress, action, &host_tx).await;
+                }
+                Some(Ok(action @ (Action::KickPlayer { .. } | Action::Unban { .. } | Action::LockRoom | Action::UnlockRoom))) => {
+                    moderate(&room, action, &host_tx).await;
+                }
+
+                // If host sends irrelevant message, tell the host
+                Some(Ok(_)) => {
+                    let error = ProtocolError::unexpected("No round is in progress");
+                    report_error(&host_tx, error).await;
+                }
+                Some(Err(error)) => report_error(&host_tx, error).await,
 
                 // If host dc's, close room
                 None => {
@@ -315,23 +708,49 @@ async fn create_room(mut host: WebSocket, state: SharedState, questions: Vec<Que
 
     tracing::debug!("Game is over!");
 
+    // Final results for everyone still in the room
+    let ranking = scoreboard.rank(&room.users.usernames());
+    let teams = room
+        .users
+        .team_members()
+        .map(|(teams, members)| rank_teams(&ranking, teams, &members));
+
     // Alert host that the game ended
     tracing::debug!("Alerting host that game has ended...");
-    let _ = host_tx.send(HostEvent::GameEnd.to_message()).await;
+    let _ = host_tx
+        .send(HostEvent::GameEnd {
+            podium: ranking.iter().take_while(|standing| standing.rank <= 3).cloned().collect(),
+            standings: ranking.clone(),
+            graded_questions: scoreboard.graded_rounds(),
+            teams: teams.clone(),
+        }.to_message())
+        .await;
 
     // Stop sending heartbeat to host
     heartbeat.abort();
 
     // Alert players game ended
-    let _ = result_tx.send(GameEvent::GameEnd);
+    let _ = result_tx.send(GameEvent::GameEnd {
+        ranking: Arc::new(ranking),
+        graded_rounds: scoreboard.graded_rounds(),
+        teams: teams.map(Arc::new),
+    });
 
     state.remove_room(&room_id).await;
 }
 
-/// Handles room joining.
+/// How a user asked to join a room.
+enum JoinRequest {
+    /// Joining as a new player.
+    New { username: String, team: Option<String> },
+    /// Coming back after losing their connection.
+    Resume { resume_token: String },
+}
+
+/// Handles room joining and rejoining.
 ///
 /// The websocket will be treated as a "player" from now on.
-async fn join_room(mut socket: WebSocket, state: SharedState, room_id: RoomId, username: String) {
+async fn join_room(mut socket: WebSocket, state: SharedState, room_id: RoomId, request: JoinRequest) {
     tracing::debug!("Finding room `{room_id}`...");
     let room = if let Some(room) = state.find_room(&room_id) {
         room
@@ -345,24 +764,65 @@ async fn join_room(mut socket: WebSocket, state: SharedState, room_id: RoomId, u
     tracing::debug!("Joining room...");
 
     let (mut user_tx, mut user_rx) = socket.split();
+
+    // Lets other tasks send events straight to this user
+    let (direct_tx, mut direct_rx) = mpsc::channel::<UserEvent>(10);
+
     // Whenever the presence gets dropped (when the function returns),
-    // a leave message is automatically sent to the host.
-    let _presence = if let Some(presence) = room.users.join_user(username.clone()).await {
-        presence
-    } else {
-        tracing::error!("User `{username}` already exists, disconnecting...");
-        let event = UserEvent::JoinFailed { reason: String::from("Duplicate user") };
-        let _ = user_tx.send(event.to_message()).await;
-        return;
+    // a leave message is automatically sent to the host once the user's
+    // grace period is over.
+    let joined = match request {
+        JoinRequest::New { username, team } => match room.check_late_join() {
+            Ok(()) => room.users.join_user(username, team, direct_tx.clone()).await,
+            Err(error) => Err(error),
+        },
+        JoinRequest::Resume { resume_token } => room.users.rejoin_user(&resume_token, direct_tx.clone()).await,
+    };
+    let JoinedUser { presence: _presence, username, team, resume_token, closed } = match joined {
+        Ok(joined) => joined,
+        Err(error) => {
+            tracing::error!("User couldn't join ({error:?}), disconnecting...");
+            let reason = match error {
+                JoinError::Duplicate => "Duplicate user",
+                JoinError::Locked => "Room is locked",
+                JoinError::Banned => "Banned from room",
+                JoinError::GameStarted => "Game has already started",
+                JoinError::RoundInProgress => "Round is in progress",
+                JoinError::UnknownToken => "Unknown resume token",
+            };
+            let event = UserEvent::JoinFailed { reason: String::from(reason) };
+            let _ = user_tx.send(event.to_message()).await;
+            return;
+        }
     };
 
     // Emit joined event to user
-    let event = UserEvent::Joined;
+    let event = UserEvent::Joined { resume_token };
     let _ = user_tx.send(event.to_message()).await;
 
+    // Tell the user which team they're on
+    if let Some(team) = team {
+        let _ = direct_tx.send(UserEvent::TeamAssigned { team }).await;
+    }
+
+    // Only game events from after joining are forwarded, so events are
+    // marked as seen before the snapshot is taken. That way nothing is
+    // missed, though an event might repeat what the snapshot said.
+    let mut event_watch = room.result_stream.clone();
+    event_watch.borrow_and_update();
+
+    // Catch up users joining a game which has already started, or coming
+    // back to one
+    if let Some(phase) = snapshot(room.phase(), &username) {
+        let (score, rank) = match find_standing(&room.standings(), &username) {
+            Some((standing, _)) => (standing.score, Some(standing.rank)),
+            None => (0, None),
+        };
+        let _ = direct_tx.send(UserEvent::Snapshot { phase, score, rank }).await;
+    }
+
     // Watch for game status updates
     let mut game_event_task = {
-        let mut event_watch = room.result_stream.clone();
         let username = username.clone();
         tokio::spawn(async move {
             loop {
@@ -370,6 +830,26 @@ async fn join_room(mut socket: WebSocket, state: SharedState, room_id: RoomId, u
                 tokio::pin!(heartbeat);
                 // Depending on which happens first
                 tokio::select! {
+                    // `biased`:
+                    // Checks the branches in order instead of randomly, so
+                    // events sent straight to this user (like an accepted
+                    // answer) arrive before game events sent after them.
+                    biased;
+
+                    // Event meant only for this user
+                    Some(event) = direct_rx.recv() => {
+                        let closing = matches!(event, UserEvent::Kicked { .. } | UserEvent::Replaced);
+                        let _ = user_tx.send(event.to_message()).await;
+
+                        // Kicked users are disconnected, which also
+                        // removes them from the room. Replaced connections
+                        // are closed too, leaving the new one in the room.
+                        if closing {
+                            tracing::debug!("`{username}` was kicked or rejoined elsewhere, closing user connection...");
+                            let _ = user_tx.close().await;
+                            return;
+                        }
+                    }
                     // Game status changed
                     res = event_watch.changed() => {
                         // Host dc'd
@@ -382,22 +862,69 @@ async fn join_room(mut socket: WebSocket, state: SharedState, room_id: RoomId, u
                         // Get event
                         let event = { event_watch.borrow().clone() };
                         match event {
-                            GameEvent::GameEnd => {
+                            GameEvent::GameEnd { ranking, graded_rounds, teams } => {
                                 tracing::debug!("Game ended, closing user connection...");
-                                let event = UserEvent::GameEnd;
+                                let (score, rank, correct) = match find_standing(&ranking, &username) {
+                                    Some((standing, _)) => (standing.score, standing.rank, standing.correct),
+                                    None => (0, ranking.len() + 1, 0),
+                                };
+                                let accuracy = (graded_rounds > 0).then(|| correct as f64 / graded_rounds as f64);
+                                let event = UserEvent::GameEnd {
+                                    score,
+                                    rank,
+                                    player_count: ranking.len(),
+                                    correct,
+                                    accuracy,
+                                    teams: teams.map(|teams| teams.to_vec()),
+                                };
                                 let _ = user_tx.send(event.to_message()).await;
                                 
                                 // Close connection
                                 let _ = user_tx.close().await;
                                 return;
                             }
-                            GameEvent::RoundBegin { choices } => {
-                                let event = UserEvent::RoundBegin { choices };
+                            GameEvent::GetReady { question, round_id, opens_at } => {
+                                let event = UserEvent::GetReady { question, round_id, opens_at };
+                                let _ = user_tx.send(event.to_message()).await;
+                            }
+                            GameEvent::RoundBegin { question, prompt, media, timing } => {
+                                let event = UserEvent::RoundBegin { question, prompt, media, timing };
+                                let _ = user_tx.send(event.to_message()).await;
+                            }
+                            GameEvent::Paused(remaining) => {
+                                let event = UserEvent::RoundPaused { remaining };
+                                let _ = user_tx.send(event.to_message()).await;
+                            }
+                            GameEvent::Resumed { remaining, deadline } => {
+                                let event = UserEvent::RoundResumed { remaining, deadline };
+                                let _ = user_tx.send(event.to_message()).await;
+                            }
+                            GameEvent::Tick { round_id, remaining } => {
+                                let event = UserEvent::Tick { round_id, remaining };
                                 let _ = user_tx.send(event.to_message()).await;
                             }
-                            GameEvent::RoundEnd { point_gains } => {
+                            GameEvent::RoundEnd { point_gains, answers, solution, ranking, teams } => {
                                 let point_gain = point_gains.get(&username).copied();
-                                let event = UserEvent::RoundEnd { point_gain };
+                                let (answer, outcome) = match answers.get(&username) {
+                                    Some((answer, outcome)) => (Some(answer.clone()), *outcome),
+                                    None => (None, AnswerOutcome::NoAnswer),
+                                };
+                                let (score, rank, gap_to_above, streak) = match find_standing(&ranking, &username) {
+                                    Some((standing, gap)) => (standing.score, standing.rank, gap, standing.streak),
+                                    None => (0, ranking.len() + 1, None, 0),
+                                };
+                                let teams = teams.map(|teams| teams.to_vec());
+                                let event = UserEvent::RoundEnd {
+                                    point_gain,
+                                    outcome,
+                                    answer,
+                                    solution,
+                                    score,
+                                    rank,
+                                    gap_to_above,
+                                    streak,
+                                    teams,
+                                };
                                 let _ = user_tx.send(event.to_message()).await;
                             }
                             GameEvent::InLobby => (),
@@ -418,32 +945,151 @@ async fn join_room(mut socket: WebSocket, state: SharedState, room_id: RoomId, u
         let action_stream = room.action_stream.clone();
         tokio::spawn(async move {
             while let Some(action) = user_rx.next_action().await {
-                if let Action::Answer { choice } = action {
-                    let _ = action_stream
-                        .send(PlayerAnswer {
-                            username: username.clone(),
-                            choice,
-                        })
-                        .await;
+                match action {
+                    Ok(Action::Answer { round_id, answer }) => {
+                        let _ = action_stream
+                            .send(PlayerAnswer {
+                                username: username.clone(),
+                                round_id,
+                                answer,
+                                received: Instant::now(),
+                            })
+                            .await;
+                    }
+                    Ok(_) => {
+                        let error = ProtocolError::unexpected("Players can only answer");
+                        let _ = direct_tx.send(UserEvent::Error(error)).await;
+                    }
+                    Err(error) => {
+                        let _ = direct_tx.send(UserEvent::Error(error)).await;
+                    }
                 }
             }
         })
     };
 
-    // Wait until either task ends
+    // Wait until either task ends, or the connection has to be closed
     tokio::select! {
         _ = (&mut game_event_task) => user_action_task.abort(),
         _ = (&mut user_action_task) => game_event_task.abort(),
+        Ok(()) = closed => {
+            tracing::debug!("Closing user connection...");
+            game_event_task.abort();
+            user_action_task.abort();
+        }
+    };
+}
+
+/// Skips, jumps to or replays a question, and tells the host what's next.
+async fn navigate(progress: &mut Progress, action: Action, host_tx: &mpsc::Sender<Message>) {
+    let result = match action {
+        Action::SkipQuestion => progress.skip(),
+        Action::JumpToQuestion { index } => progress.jump_to(index),
+        Action::ReplayQuestion { index, points } => progress.replay(index, points),
+        _ => unreachable!("`{action:?}` isn't a navigation action"),
+    };
+
+    match result {
+        Ok(()) => {
+            let event = HostEvent::NextQuestion {
+                index: progress.next(),
+                total: progress.total(),
+            };
+            let _ = host_tx.send(event.to_message()).await;
+        }
+        Err(message) => report_error(host_tx, ProtocolError::new(ErrorCode::InvalidAction, message)).await,
+    }
+}
+
+/// Kicks or bans a player, lifts a ban, or locks or unlocks the room, and
+/// tells the host how it went.
+async fn moderate(room: &Room, action: Action, host_tx: &mpsc::Sender<Message>) {
+    let event = match action {
+        Action::KickPlayer { username, ban } => {
+            // The host is told about kicked players along with them leaving
+            if room.users.kick(&username, ban).await {
+                return;
+            }
+
+            // Banning works for names nobody is using, kicking doesn't
+            if !ban {
+                let error = ProtocolError::new(ErrorCode::InvalidAction, "There is no player with that name");
+                return report_error(host_tx, error).await;
+            }
+            HostEvent::PlayerKicked { username, banned: ban }
+        }
+        Action::Unban { username } => {
+            if !room.users.unban(&username) {
+                let error = ProtocolError::new(ErrorCode::InvalidAction, "That name isn't banned");
+                return report_error(host_tx, error).await;
+            }
+            HostEvent::Unbanned { username }
+        }
+        Action::LockRoom | Action::UnlockRoom => {
+            let locked = matches!(action, Action::LockRoom);
+            room.users.set_locked(locked);
+            HostEvent::LockChanged { locked }
+        }
+        _ => unreachable!("`{action:?}` isn't a moderation action"),
     };
+
+    tracing::debug!("Moderation: {event:?}");
+    let _ = host_tx.send(event.to_message()).await;
+}
+
+/// Describes a game's phase for a player joining late or rejoining, or `None`
+/// if the game hasn't started.
+fn snapshot(phase: Phase, username: &str) -> Option<GamePhase> {
+    let phase = match phase {
+        Phase::Lobby => return None,
+        Phase::GetReady { question, round_id, opens_at } => GamePhase::GetReady { question, round_id, opens_at },
+        Phase::Round { question, prompt, media, round_id, ends, paused, answered } => {
+            let remaining = paused.unwrap_or_else(|| ends.saturating_duration_since(Instant::now()));
+            let server_time = SystemTime::now();
+            GamePhase::Round {
+                question,
+                prompt,
+                media,
+                timing: RoundTiming {
+                    round_id,
+                    server_time: unix_millis(server_time),
+                    deadline: unix_millis(server_time + remaining),
+                },
+                remaining: remaining.as_secs_f64(),
+                paused: paused.is_some(),
+                answered: answered.contains(username),
+            }
+        }
+        Phase::BetweenRounds => GamePhase::BetweenRounds,
+    };
+
+    Some(phase)
+}
+
+/// Tells a player why their answer wasn't counted.
+fn reject_answer(room: &Room, username: &str, reason: RejectReason) {
+    tracing::debug!("Rejected answer from `{username}`: {reason:?}");
+    room.users.send_to(username, UserEvent::AnswerRejected { reason });
+}
+
+/// Converts a time to milliseconds since the unix epoch.
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+/// Tells the host that the server couldn't accept its message.
+async fn report_error(host_tx: &mpsc::Sender<Message>, error: ProtocolError) {
+    tracing::debug!("Host error: {}", error.message);
+    let _ = host_tx.send(HostEvent::Error(error).to_message()).await;
 }
 
 /// Websocket api testing
 #[cfg(test)]
 mod tests {
     use crate::ws::router;
-    use crate::ws::api::{Action, HostEvent, UserEvent, Question};
+    use crate::ws::api::{Action, HostEvent, UserEvent, GamePhase, LateJoin, Question, QuestionKind, Prompt, Submission, MultiSelectScoring, Normalization, Choice, Media, MediaSource, ErrorCode, ReplayPoints, RejectReason, RoomSettings, Solution, AnswerOutcome, ScoringSettings, Standing, PointMultiplier, TeamSettings, TeamAssignment, TeamScoring};
 
-    use std::collections::HashSet;
+    use std::collections::{HashMap, HashSet};
     use std::sync::atomic::{AtomicU16, Ordering};
     use std::{net::SocketAddr, time::Duration};
     use tokio::net::TcpStream;
@@ -495,10 +1141,14 @@ mod tests {
         }
 
         async fn create_room(&self, questions: Vec<Question>) -> (HostSocket, RoomId) {
+            self.create_room_with(questions, RoomSettings::default()).await
+        }
+
+        async fn create_room_with(&self, questions: Vec<Question>, settings: RoomSettings) -> (HostSocket, RoomId) {
             let mut ws = self.connect().await;
 
             // Send create room action
-            ws.send(serial(&Action::CreateRoom { questions })).await.unwrap();
+            ws.send(serial(&Action::CreateRoom { questions, settings })).await.unwrap();
 
             // Response must be a text message with no errors
             let_assert!(Some(Ok(Message::Text(s))) = ws.next().await);
@@ -513,6 +1163,10 @@ mod tests {
         }
 
         async fn join_room(&self, room_id: RoomId, username: String) -> UserSocket {
+            self.join_team(room_id, username, None).await
+        }
+
+        async fn join_team(&self, room_id: RoomId, username: String, team: Option<String>) -> UserSocket {
             // Establish connection
             let mut ws = self.connect().await;
 
@@ -520,10 +1174,18 @@ mod tests {
             ws.send(serial(&Action::JoinRoom {
                 room_id,
                 username,
+                team,
             })).await.unwrap();
 
             UserSocket(ws)
         }
+
+        async fn rejoin(&self, room_id: RoomId, resume_token: String) -> UserSocket {
+            let mut ws = self.connect().await;
+            ws.send(serial(&Action::Rejoin { room_id, resume_token })).await.unwrap();
+
+            UserSocket(ws)
+        }
     }
 
     impl HostSocket {
@@ -571,7 +1233,7 @@ mod tests {
                 let mut choices = Vec::new();
 
                 $(
-                    choices.push(String::from($choice));
+                    choices.push(Choice::from($choice));
                     if $correct {
                         answer_count += 1;
                     } else {
@@ -583,9 +1245,10 @@ mod tests {
 
                 Question {
                     question: String::from($ques),
+                    media: None,
                     time: $time,
-                    choices,
-                    answer,
+                    points: PointMultiplier::Standard,
+                    kind: QuestionKind::MultipleChoice { choices, answer },
                 }
             }
         };
@@ -612,7 +1275,7 @@ mod tests {
         let question_clone = question.clone();
         let host_task = tokio::spawn(async move {
             // User joined event
-            let_assert!(HostEvent::UserJoined { username } = host_ws.recv().await.unwrap());
+            let_assert!(HostEvent::UserJoined { username, team: None } = host_ws.recv().await.unwrap());
 
             // Username matches
             assert_eq!("Johnny", &username);
@@ -621,59 +1284,917 @@ mod tests {
             host_ws.send(&Action::BeginRound).await;
 
             // Round begin event
-            let_assert!(HostEvent::RoundBegin { question } = host_ws.recv().await.unwrap());
+            let_assert!(HostEvent::RoundBegin { question, index: 0, total: 1, timing } = host_ws.recv().await.unwrap());
+
+            // The deadline matches the question's time
+            assert_eq!(timing.round_id, 1);
+            assert_eq!(timing.deadline - timing.server_time, 30_000);
 
             // Check if the question is the same
             assert_eq!(question_clone, question);
 
             // User answered event
-            let_assert!(HostEvent::UserAnswered { username } = host_ws.recv().await.unwrap());
+            let_assert!(HostEvent::UserAnswered { username, .. } = host_ws.recv().await.unwrap());
 
             // Username matches
             assert_eq!("Johnny", &username);
 
-            // Round end event
-            let_assert!(HostEvent::RoundEnd { point_gains } = host_ws.recv().await.unwrap());
+            // Round end event, with how many picked each choice and which
+            // was correct
+            let_assert!(HostEvent::RoundEnd { point_gains, choice_counts, solution, .. } = host_ws.recv().await.unwrap());
+            assert_eq!(choice_counts, Some(vec![0, 1]));
+            assert_eq!(solution, Some(Solution::Choice { choice: 1 }));
+
+            // Johnny answered right away, so he gained (almost) 1000 points
+            let_assert!(Some(&gain) = point_gains.get("Johnny"));
+            assert_quick(gain, 1000);
 
-            // Johnny gained 1000 points
-            assert_eq!(point_gains.get("Johnny"), Some(&1000));
+            // Leaderboard event
+            let_assert!(HostEvent::Leaderboard { top, player_count: 1 } = host_ws.recv().await.unwrap());
+            assert_eq!(top, vec![Standing { username: String::from("Johnny"), score: gain, correct: 1, streak: 1, rank: 1 }]);
 
             // Send begin round action
             host_ws.send(&Action::BeginRound).await;
 
-            // Game end event
-            let_assert!(HostEvent::GameEnd = host_ws.recv().await.unwrap());
+            // Game end event, Johnny won
+            let_assert!(HostEvent::GameEnd { podium, standings, graded_questions: 1, teams: None } = host_ws.recv().await.unwrap());
+            assert_eq!(podium, standings);
+            assert_eq!(podium[0].username, "Johnny");
         });
 
         // Player tests
         let mut user_ws = server.join_room(room_id, String::from("Johnny")).await;
         let user_task = tokio::spawn(async move {
+            let_assert!(QuestionKind::MultipleChoice { choices: question_choices, answer } = question.kind);
+
             // Joined event
-            assert_eq!(user_ws.recv().await.unwrap(), UserEvent::Joined);
+            let_assert!(UserEvent::Joined { .. } = user_ws.recv().await.unwrap());
 
             // Round begin event
-            let_assert!(UserEvent::RoundBegin { choices } = user_ws.recv().await.unwrap());
+            let_assert!(UserEvent::RoundBegin { prompt: Prompt::MultipleChoice { choices }, .. } = user_ws.recv().await.unwrap());
 
             // Has correct choice count
-            assert_eq!(question.choices, choices);
+            assert_eq!(question_choices, choices);
 
-            // Send correct answer
-            user_ws.send(&Action::Answer { choice: question.answer }).await;
+            // Send correct answer, which the server accepts
+            user_ws.send(&Action::Answer { round_id: 1, answer: Submission::Choice { choice: answer } }).await;
+            assert_eq!(user_ws.recv().await.unwrap(), UserEvent::AnswerAccepted);
 
             // Round end event
-            let_assert!(UserEvent::RoundEnd { point_gain: Some(point_gain) } = user_ws.recv().await.unwrap());
-
-            // Gained 1000 points
-            assert_eq!(point_gain, 1000);
+            let_assert!(UserEvent::RoundEnd {
+                point_gain: Some(point_gain),
+                outcome: AnswerOutcome::Correct,
+                answer: Some(Submission::Choice { choice }),
+                solution: Some(Solution::Choice { choice: solution }),
+                score,
+                rank,
+                gap_to_above,
+                streak: 1,
+                teams: None,
+            } = user_ws.recv().await.unwrap());
+            assert_eq!((choice, solution), (answer, answer));
+
+            // Gained (almost) 1000 points, and is in first place
+            assert_quick(point_gain, 1000);
+            assert_eq!((score, rank, gap_to_above), (point_gain, 1, None));
 
             // Game end event
-            let_assert!(UserEvent::GameEnd = user_ws.recv().await.unwrap());
+            let_assert!(UserEvent::GameEnd { score: final_score, rank: 1, player_count: 1, correct: 1, accuracy, teams: None } = user_ws.recv().await.unwrap());
+            assert_eq!(final_score, point_gain);
+            assert_eq!(accuracy, Some(1.0));
         });
 
         // Wait for both tasks to complete
         tokio::try_join!(host_task, user_task).unwrap();
     }
 
+    /// Tests that multi-select questions give partial credit.
+    #[tokio::test]
+    async fn multi_select_partial_credit() {
+        let server = TestServer::new().await;
+
+        let question = Question {
+            question: String::from("Which are fish?"),
+            media: None,
+            time: 30,
+            points: PointMultiplier::Standard,
+            kind: QuestionKind::MultiSelect {
+                choices: vec!["salmon".into(), "whale".into(), "tuna".into()],
+                answers: vec![0, 2],
+                scoring: MultiSelectScoring::Partial,
+            },
+        };
+
+        let (mut host_ws, room_id) = server.create_room(vec![question]).await;
+
+        let host_task = tokio::spawn(async move {
+            let_assert!(HostEvent::UserJoined { .. } = host_ws.recv().await.unwrap());
+            host_ws.send(&Action::BeginRound).await;
+
+            let_assert!(HostEvent::RoundBegin { .. } = host_ws.recv().await.unwrap());
+            let_assert!(HostEvent::UserAnswered { .. } = host_ws.recv().await.unwrap());
+
+            // Half of the correct choices were picked
+            let_assert!(HostEvent::RoundEnd { point_gains, .. } = host_ws.recv().await.unwrap());
+            assert_quick(point_gains["Johnny"], 500);
+        });
+
+        let mut user_ws = server.join_room(room_id, String::from("Johnny")).await;
+        let user_task = tokio::spawn(async move {
+            let_assert!(UserEvent::Joined { .. } = user_ws.recv().await.unwrap());
+
+            let_assert!(UserEvent::RoundBegin { prompt: Prompt::MultiSelect { choices }, .. } = user_ws.recv().await.unwrap());
+            assert_eq!(choices.len(), 3);
+
+            // A single choice doesn't fit a multi-select question, so it is rejected
+            user_ws.send(&Action::Answer { round_id: 1, answer: Submission::Choice { choice: 0 } }).await;
+            assert_eq!(user_ws.recv().await.unwrap(), UserEvent::AnswerRejected { reason: RejectReason::Invalid });
+            user_ws.send(&Action::Answer { round_id: 1, answer: Submission::Choices { choices: vec![0] } }).await;
+            assert_eq!(user_ws.recv().await.unwrap(), UserEvent::AnswerAccepted);
+
+            let_assert!(UserEvent::RoundEnd { point_gain: Some(point_gain), .. } = user_ws.recv().await.unwrap());
+            assert_quick(point_gain, 500);
+        });
+
+        tokio::try_join!(host_task, user_task).unwrap();
+    }
+
+    /// Tests that players can tell wrong answers apart from not answering.
+    #[tokio::test]
+    async fn answer_reveal() {
+        let server = TestServer::new().await;
+
+        let question = question! {
+            "Fish?", time: 30 => [
+                false => "foo",
+                true => "bar",
+            ]
+        };
+
+        let (mut host_ws, room_id) = server.create_room(vec![question]).await;
+        let mut alice_ws = server.join_room(room_id, String::from("Alice")).await;
+        let_assert!(HostEvent::UserJoined { .. } = host_ws.recv().await.unwrap());
+        let mut bob_ws = server.join_room(room_id, String::from("Bob")).await;
+        let_assert!(HostEvent::UserJoined { .. } = host_ws.recv().await.unwrap());
+
+        host_ws.send(&Action::BeginRound).await;
+        let_assert!(HostEvent::RoundBegin { .. } = host_ws.recv().await.unwrap());
+
+        // Alice answers wrong, Bob doesn't answer
+        alice_ws.send(&Action::Answer { round_id: 1, answer: Submission::Choice { choice: 0 } }).await;
+        let_assert!(HostEvent::UserAnswered { choice_counts: None, .. } = host_ws.recv().await.unwrap());
+
+        // Alice can't change her answer
+        alice_ws.send(&Action::Answer { round_id: 1, answer: Submission::Choice { choice: 1 } }).await;
+        let_assert!(UserEvent::Joined { .. } = alice_ws.recv().await.unwrap());
+        let_assert!(UserEvent::RoundBegin { .. } = alice_ws.recv().await.unwrap());
+        assert_eq!(alice_ws.recv().await.unwrap(), UserEvent::AnswerAccepted);
+        assert_eq!(alice_ws.recv().await.unwrap(), UserEvent::AnswerRejected { reason: RejectReason::AlreadyAnswered });
+        host_ws.send(&Action::EndRound).await;
+
+        let_assert!(HostEvent::RoundEnd { choice_counts, solution, .. } = host_ws.recv().await.unwrap());
+        assert_eq!(choice_counts, Some(vec![1, 0]));
+        assert_eq!(solution, Some(Solution::Choice { choice: 1 }));
+
+        let_assert!(UserEvent::RoundEnd { outcome: AnswerOutcome::Wrong, answer: Some(_), solution: Some(_), .. } = alice_ws.recv().await.unwrap());
+
+        let_assert!(UserEvent::Joined { .. } = bob_ws.recv().await.unwrap());
+        let_assert!(UserEvent::RoundBegin { .. } = bob_ws.recv().await.unwrap());
+        let_assert!(UserEvent::RoundEnd { outcome: AnswerOutcome::NoAnswer, answer: None, .. } = bob_ws.recv().await.unwrap());
+
+        // No round is in progress now
+        bob_ws.send(&Action::Answer { round_id: 1, answer: Submission::Choice { choice: 1 } }).await;
+        assert_eq!(bob_ws.recv().await.unwrap(), UserEvent::AnswerRejected { reason: RejectReason::NoRound });
+    }
+
+    /// Tests that answers for a round other than the open one never count.
+    #[tokio::test]
+    async fn stale_answers() {
+        let server = TestServer::new().await;
+
+        let question = question! {
+            "Fish?", time: 30 => [
+                false => "foo",
+                true => "bar",
+            ]
+        };
+
+        let (mut host_ws, room_id) = server.create_room(vec![question.clone(), question]).await;
+        let mut user_ws = server.join_room(room_id, String::from("Johnny")).await;
+        let_assert!(HostEvent::UserJoined { .. } = host_ws.recv().await.unwrap());
+        let_assert!(UserEvent::Joined { .. } = user_ws.recv().await.unwrap());
+
+        // The first round ends before Johnny answers it
+        host_ws.send(&Action::BeginRound).await;
+        let_assert!(HostEvent::RoundBegin { timing, .. } = host_ws.recv().await.unwrap());
+        let first_round = timing.round_id;
+        host_ws.send(&Action::EndRound).await;
+        let_assert!(HostEvent::RoundEnd { .. } = host_ws.recv().await.unwrap());
+        let_assert!(HostEvent::Leaderboard { .. } = host_ws.recv().await.unwrap());
+        let_assert!(UserEvent::RoundBegin { .. } = user_ws.recv().await.unwrap());
+        let_assert!(UserEvent::RoundEnd { .. } = user_ws.recv().await.unwrap());
+
+        host_ws.send(&Action::BeginRound).await;
+        let_assert!(HostEvent::RoundBegin { timing, .. } = host_ws.recv().await.unwrap());
+        assert_eq!(timing.round_id, first_round + 1);
+        let_assert!(UserEvent::RoundBegin { timing: user_timing, .. } = user_ws.recv().await.unwrap());
+        assert_eq!(user_timing.round_id, timing.round_id);
+
+        // The late answer for the first round arrives during the second
+        user_ws.send(&Action::Answer { round_id: first_round, answer: Submission::Choice { choice: 1 } }).await;
+        assert_eq!(user_ws.recv().await.unwrap(), UserEvent::AnswerRejected { reason: RejectReason::WrongRound });
+
+        user_ws.send(&Action::Answer { round_id: timing.round_id, answer: Submission::Choice { choice: 1 } }).await;
+        assert_eq!(user_ws.recv().await.unwrap(), UserEvent::AnswerAccepted);
+        let_assert!(HostEvent::UserAnswered { .. } = host_ws.recv().await.unwrap());
+        let_assert!(HostEvent::RoundEnd { point_gains, .. } = host_ws.recv().await.unwrap());
+        assert!(point_gains.contains_key("Johnny"));
+    }
+
+    /// Tests that the room's scoring strategy decides what answers are worth.
+    #[tokio::test]
+    async fn scoring_strategy() {
+        let server = TestServer::new().await;
+
+        let question = question! {
+            "Fish?", time: 30 => [
+                false => "foo",
+                true => "bar",
+            ]
+        };
+        let settings = RoomSettings {
+            scoring: ScoringSettings::OrderBased { first_points: 1000 },
+            ..RoomSettings::default()
+        };
+
+        let (mut host_ws, room_id) = server.create_room_with(vec![question], settings).await;
+
+        let mut johnny_ws = server.join_room(room_id, String::from("Johnny")).await;
+        let_assert!(HostEvent::UserJoined { .. } = host_ws.recv().await.unwrap());
+        let mut jane_ws = server.join_room(room_id, String::from("Jane")).await;
+        let_assert!(HostEvent::UserJoined { .. } = host_ws.recv().await.unwrap());
+
+        host_ws.send(&Action::BeginRound).await;
+        let_assert!(HostEvent::RoundBegin { .. } = host_ws.recv().await.unwrap());
+
+        // Johnny answers first, then Jane
+        johnny_ws.send(&Action::Answer { round_id: 1, answer: Submission::Choice { choice: 1 } }).await;
+        let_assert!(HostEvent::UserAnswered { .. } = host_ws.recv().await.unwrap());
+        jane_ws.send(&Action::Answer { round_id: 1, answer: Submission::Choice { choice: 1 } }).await;
+        let_assert!(HostEvent::UserAnswered { .. } = host_ws.recv().await.unwrap());
+
+        // The second correct answer earns 10/11 of the first
+        let_assert!(HostEvent::RoundEnd { point_gains, .. } = host_ws.recv().await.unwrap());
+        assert_eq!(point_gains, HashMap::from([(String::from("Johnny"), 1000), (String::from("Jane"), 909)]));
+    }
+
+    /// Tests team assignment and team scores.
+    #[tokio::test]
+    async fn teams() {
+        let server = TestServer::new().await;
+
+        let question = question! {
+            "Fish?", time: 30 => [
+                false => "foo",
+                true => "bar",
+            ]
+        };
+        let settings = RoomSettings {
+            teams: Some(TeamSettings {
+                names: vec![String::from("Red"), String::from("Blue")],
+                assignment: TeamAssignment::Balanced,
+                scoring: TeamScoring::Sum,
+            }),
+            ..RoomSettings::default()
+        };
+
+        let (mut host_ws, room_id) = server.create_room_with(vec![question], settings).await;
+
+        // Alice picks blue, Bob doesn't pick so he joins the smaller team
+        let mut alice_ws = server.join_team(room_id, String::from("Alice"), Some(String::from("Blue"))).await;
+        let_assert!(HostEvent::UserJoined { team: Some(team), .. } = host_ws.recv().await.unwrap());
+        assert_eq!(team, "Blue");
+        let mut bob_ws = server.join_room(room_id, String::from("Bob")).await;
+        let_assert!(HostEvent::UserJoined { team: Some(team), .. } = host_ws.recv().await.unwrap());
+        assert_eq!(team, "Red");
+
+        let_assert!(UserEvent::Joined { .. } = bob_ws.recv().await.unwrap());
+        assert_eq!(bob_ws.recv().await.unwrap(), UserEvent::TeamAssigned { team: String::from("Red") });
+
+        // The host moves Bob onto Alice's team
+        host_ws.send(&Action::AssignTeam { username: String::from("Bob"), team: String::from("Blue") }).await;
+        let_assert!(HostEvent::TeamAssigned { .. } = host_ws.recv().await.unwrap());
+        assert_eq!(bob_ws.recv().await.unwrap(), UserEvent::TeamAssigned { team: String::from("Blue") });
+
+        host_ws.send(&Action::AssignTeam { username: String::from("Bob"), team: String::from("Green") }).await;
+        let_assert!(HostEvent::Error(error) = host_ws.recv().await.unwrap());
+        assert_eq!(error.code, ErrorCode::InvalidAction);
+
+        host_ws.send(&Action::BeginRound).await;
+        let_assert!(HostEvent::RoundBegin { .. } = host_ws.recv().await.unwrap());
+
+        alice_ws.send(&Action::Answer { round_id: 1, answer: Submission::Choice { choice: 1 } }).await;
+        bob_ws.send(&Action::Answer { round_id: 1, answer: Submission::Choice { choice: 1 } }).await;
+        let_assert!(HostEvent::UserAnswered { .. } = host_ws.recv().await.unwrap());
+        let_assert!(HostEvent::UserAnswered { .. } = host_ws.recv().await.unwrap());
+
+        // Both players' points count towards blue
+        let_assert!(HostEvent::RoundEnd { point_gains, teams: Some(teams), .. } = host_ws.recv().await.unwrap());
+        assert_eq!(teams[0].team, "Blue");
+        assert_eq!(teams[0].score, point_gains.values().sum::<u32>());
+        assert_eq!((teams[0].members, teams[1].members), (2, 0));
+
+        let_assert!(UserEvent::RoundBegin { .. } = bob_ws.recv().await.unwrap());
+        assert_eq!(bob_ws.recv().await.unwrap(), UserEvent::AnswerAccepted);
+        let_assert!(UserEvent::RoundEnd { teams: Some(player_teams), .. } = bob_ws.recv().await.unwrap());
+        assert_eq!(player_teams, teams);
+    }
+
+    /// Tests that pausing freezes the round timer and rejects answers.
+    #[tokio::test]
+    async fn pause_resume() {
+        let server = TestServer::new().await;
+
+        let question = question! {
+            "Fish?", time: 2 => [
+                false => "foo",
+                true => "bar",
+            ]
+        };
+
+        let (mut host_ws, room_id) = server.create_room(vec![question]).await;
+        let mut user_ws = server.join_room(room_id, String::from("Johnny")).await;
+        let_assert!(HostEvent::UserJoined { .. } = host_ws.recv().await.unwrap());
+        let_assert!(UserEvent::Joined { .. } = user_ws.recv().await.unwrap());
+
+        host_ws.send(&Action::BeginRound).await;
+        let_assert!(HostEvent::RoundBegin { .. } = host_ws.recv().await.unwrap());
+        let_assert!(UserEvent::RoundBegin { .. } = user_ws.recv().await.unwrap());
+
+        // Can't resume a round which isn't paused
+        host_ws.send(&Action::Resume).await;
+        let_assert!(HostEvent::Error(error) = host_ws.recv().await.unwrap());
+        assert_eq!(error.code, ErrorCode::UnexpectedAction);
+
+        host_ws.send(&Action::Pause).await;
+        let_assert!(HostEvent::RoundPaused { remaining } = host_ws.recv().await.unwrap());
+        assert!(remaining > 1.0 && remaining <= 2.0);
+        assert_eq!(user_ws.recv().await.unwrap(), UserEvent::RoundPaused { remaining });
+
+        // The answer is rejected, and the round outlasts its time
+        user_ws.send(&Action::Answer { round_id: 1, answer: Submission::Choice { choice: 1 } }).await;
+        assert_eq!(user_ws.recv().await.unwrap(), UserEvent::AnswerRejected { reason: RejectReason::Paused });
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+
+        host_ws.send(&Action::Resume).await;
+        let_assert!(HostEvent::RoundResumed { remaining: resumed, deadline } = host_ws.recv().await.unwrap());
+        assert_eq!(resumed, remaining);
+        assert_eq!(user_ws.recv().await.unwrap(), UserEvent::RoundResumed { remaining, deadline });
+
+        user_ws.send(&Action::Answer { round_id: 1, answer: Submission::Choice { choice: 1 } }).await;
+        let_assert!(HostEvent::UserAnswered { .. } = host_ws.recv().await.unwrap());
+
+        // Time spent paused doesn't make the answer slower
+        let_assert!(HostEvent::RoundEnd { point_gains, .. } = host_ws.recv().await.unwrap());
+        assert!(point_gains["Johnny"] > 900);
+    }
+
+    /// Tests skipping, jumping to and replaying questions.
+    #[tokio::test]
+    async fn navigation() {
+        let server = TestServer::new().await;
+
+        let question = question! {
+            "Fish?", time: 30 => [
+                false => "foo",
+                true => "bar",
+            ]
+        };
+
+        let (mut host_ws, room_id) = server.create_room(vec![question.clone(), question.clone(), question]).await;
+        let mut user_ws = server.join_room(room_id, String::from("Johnny")).await;
+        let_assert!(HostEvent::UserJoined { .. } = host_ws.recv().await.unwrap());
+        let_assert!(UserEvent::Joined { .. } = user_ws.recv().await.unwrap());
+
+        // Only questions which were played can be replayed
+        host_ws.send(&Action::ReplayQuestion { index: 0, points: ReplayPoints::Overwrite }).await;
+        let_assert!(HostEvent::Error(error) = host_ws.recv().await.unwrap());
+        assert_eq!(error.code, ErrorCode::InvalidAction);
+        host_ws.send(&Action::JumpToQuestion { index: 3 }).await;
+        let_assert!(HostEvent::Error(_) = host_ws.recv().await.unwrap());
+
+        // Start from the second question
+        host_ws.send(&Action::JumpToQuestion { index: 1 }).await;
+        let_assert!(HostEvent::NextQuestion { index: Some(1), total: 3 } = host_ws.recv().await.unwrap());
+
+        host_ws.send(&Action::BeginRound).await;
+        let_assert!(HostEvent::RoundBegin { index: 1, total: 3, .. } = host_ws.recv().await.unwrap());
+        let_assert!(UserEvent::RoundBegin { .. } = user_ws.recv().await.unwrap());
+        user_ws.send(&Action::Answer { round_id: 1, answer: Submission::Choice { choice: 1 } }).await;
+        let_assert!(HostEvent::UserAnswered { .. } = host_ws.recv().await.unwrap());
+        let_assert!(HostEvent::RoundEnd { .. } = host_ws.recv().await.unwrap());
+        let_assert!(HostEvent::Leaderboard { top, .. } = host_ws.recv().await.unwrap());
+        assert!(top[0].score > 0);
+
+        // Replay it without answering, so the earlier points are lost
+        host_ws.send(&Action::ReplayQuestion { index: 1, points: ReplayPoints::Overwrite }).await;
+        let_assert!(HostEvent::NextQuestion { index: Some(1), .. } = host_ws.recv().await.unwrap());
+
+        host_ws.send(&Action::BeginRound).await;
+        let_assert!(HostEvent::RoundBegin { index: 1, .. } = host_ws.recv().await.unwrap());
+        host_ws.send(&Action::EndRound).await;
+        let_assert!(HostEvent::RoundEnd { .. } = host_ws.recv().await.unwrap());
+        let_assert!(HostEvent::Leaderboard { top, .. } = host_ws.recv().await.unwrap());
+        assert_eq!((top[0].score, top[0].correct), (0, 0));
+
+        // Skipping the last question ends the game
+        host_ws.send(&Action::SkipQuestion).await;
+        let_assert!(HostEvent::NextQuestion { index: None, total: 3 } = host_ws.recv().await.unwrap());
+        host_ws.send(&Action::BeginRound).await;
+        let_assert!(HostEvent::GameEnd { graded_questions: 1, .. } = host_ws.recv().await.unwrap());
+    }
+
+    /// Tests that players are told whether each answer counted.
+    #[tokio::test]
+    async fn answer_acks() {
+        let server = TestServer::new().await;
+        let (mut host_ws, room_id) = server.create_room(vec![
+            question! {
+                "Fish?", time: 30 => [
+                    false => "foo",
+                    true => "bar",
+                ]
+            }
+        ]).await;
+
+        let mut alice = server.join_room(room_id, String::from("Alice")).await;
+        let_assert!(UserEvent::Joined { .. } = alice.recv().await.unwrap());
+        let mut bob = server.join_room(room_id, String::from("Bob")).await;
+        let_assert!(UserEvent::Joined { .. } = bob.recv().await.unwrap());
+        let_assert!(HostEvent::UserJoined { .. } = host_ws.recv().await.unwrap());
+        let_assert!(HostEvent::UserJoined { .. } = host_ws.recv().await.unwrap());
+
+        let answer = |round_id, choice| Action::Answer { round_id, answer: Submission::Choice { choice } };
+
+        // Nothing to answer in the lobby
+        alice.send(&answer(1, 1)).await;
+        let_assert!(UserEvent::AnswerRejected { reason: RejectReason::NoRound } = alice.recv().await.unwrap());
+
+        host_ws.send(&Action::BeginRound).await;
+        let_assert!(HostEvent::RoundBegin { .. } = host_ws.recv().await.unwrap());
+        let_assert!(UserEvent::RoundBegin { .. } = alice.recv().await.unwrap());
+
+        let cases = [
+            (answer(2, 1), RejectReason::WrongRound),
+            (answer(1, 5), RejectReason::Invalid),
+        ];
+        for (action, expected) in cases {
+            alice.send(&action).await;
+            let_assert!(UserEvent::AnswerRejected { reason } = alice.recv().await.unwrap());
+            assert_eq!(reason, expected);
+        }
+
+        // Only the first valid answer counts
+        alice.send(&answer(1, 1)).await;
+        let_assert!(UserEvent::AnswerAccepted = alice.recv().await.unwrap());
+        let_assert!(HostEvent::UserAnswered { .. } = host_ws.recv().await.unwrap());
+        alice.send(&answer(1, 0)).await;
+        let_assert!(UserEvent::AnswerRejected { reason: RejectReason::AlreadyAnswered } = alice.recv().await.unwrap());
+    }
+
+    /// Tests that the game carries on from where it was after a replay.
+    #[tokio::test]
+    async fn replay_resumes() {
+        let server = TestServer::new().await;
+
+        let question = question! {
+            "Fish?", time: 30 => [
+                false => "foo",
+                true => "bar",
+            ]
+        };
+
+        let (mut host_ws, room_id) = server.create_room(vec![question; 4]).await;
+        let mut user_ws = server.join_room(room_id, String::from("Johnny")).await;
+        let_assert!(HostEvent::UserJoined { .. } = host_ws.recv().await.unwrap());
+        let_assert!(UserEvent::Joined { .. } = user_ws.recv().await.unwrap());
+
+        // Answer the first three questions
+        let mut score = 0;
+        for round_id in 1..=3 {
+            host_ws.send(&Action::BeginRound).await;
+            let_assert!(HostEvent::RoundBegin { index, .. } = host_ws.recv().await.unwrap());
+            assert_eq!(index, round_id as usize - 1);
+            let_assert!(UserEvent::RoundBegin { .. } = user_ws.recv().await.unwrap());
+            user_ws.send(&Action::Answer { round_id, answer: Submission::Choice { choice: 1 } }).await;
+            let_assert!(UserEvent::AnswerAccepted = user_ws.recv().await.unwrap());
+            let_assert!(HostEvent::UserAnswered { .. } = host_ws.recv().await.unwrap());
+            let_assert!(HostEvent::RoundEnd { .. } = host_ws.recv().await.unwrap());
+            let_assert!(HostEvent::Leaderboard { top, .. } = host_ws.recv().await.unwrap());
+            assert!(top[0].score > score);
+            score = top[0].score;
+            let_assert!(UserEvent::RoundEnd { .. } = user_ws.recv().await.unwrap());
+        }
+
+        // Replay the first question for practice, without answering
+        host_ws.send(&Action::ReplayQuestion { index: 0, points: ReplayPoints::Ignore }).await;
+        let_assert!(HostEvent::NextQuestion { index: Some(0), .. } = host_ws.recv().await.unwrap());
+        host_ws.send(&Action::BeginRound).await;
+        let_assert!(HostEvent::RoundBegin { index: 0, .. } = host_ws.recv().await.unwrap());
+        host_ws.send(&Action::EndRound).await;
+        let_assert!(HostEvent::RoundEnd { .. } = host_ws.recv().await.unwrap());
+        let_assert!(HostEvent::Leaderboard { top, .. } = host_ws.recv().await.unwrap());
+        assert_eq!((top[0].score, top[0].correct), (score, 3));
+
+        // The game carries on with the fourth question, and the second and
+        // third keep their points
+        host_ws.send(&Action::BeginRound).await;
+        let_assert!(HostEvent::RoundBegin { index: 3, .. } = host_ws.recv().await.unwrap());
+        host_ws.send(&Action::EndRound).await;
+        let_assert!(HostEvent::RoundEnd { .. } = host_ws.recv().await.unwrap());
+        let_assert!(HostEvent::Leaderboard { top, .. } = host_ws.recv().await.unwrap());
+        assert_eq!((top[0].score, top[0].correct), (score, 3));
+
+        // Jumping back to a played question replays it once, then the game ends
+        host_ws.send(&Action::JumpToQuestion { index: 1 }).await;
+        let_assert!(HostEvent::NextQuestion { index: Some(1), .. } = host_ws.recv().await.unwrap());
+        host_ws.send(&Action::BeginRound).await;
+        let_assert!(HostEvent::RoundBegin { index: 1, .. } = host_ws.recv().await.unwrap());
+        host_ws.send(&Action::EndRound).await;
+        let_assert!(HostEvent::RoundEnd { .. } = host_ws.recv().await.unwrap());
+        let_assert!(HostEvent::Leaderboard { .. } = host_ws.recv().await.unwrap());
+        host_ws.send(&Action::BeginRound).await;
+        let_assert!(HostEvent::GameEnd { .. } = host_ws.recv().await.unwrap());
+    }
+
+    /// Tests that rounds send the time left when ticks are turned on.
+    #[tokio::test]
+    async fn ticks() {
+        let server = TestServer::new().await;
+
+        let question = question! {
+            "Fish?", time: 3 => [
+                false => "foo",
+                true => "bar",
+            ]
+        };
+        let settings = RoomSettings {
+            tick_interval: Some(1),
+            ..RoomSettings::default()
+        };
+
+        let (mut host_ws, room_id) = server.create_room_with(vec![question], settings).await;
+        let mut user_ws = server.join_room(room_id, String::from("Johnny")).await;
+        let_assert!(HostEvent::UserJoined { .. } = host_ws.recv().await.unwrap());
+        let_assert!(UserEvent::Joined { .. } = user_ws.recv().await.unwrap());
+
+        host_ws.send(&Action::BeginRound).await;
+        let_assert!(HostEvent::RoundBegin { timing, .. } = host_ws.recv().await.unwrap());
+        let_assert!(UserEvent::RoundBegin { timing: user_timing, .. } = user_ws.recv().await.unwrap());
+        assert_eq!(timing, user_timing);
+
+        // A tick comes each second until the round ends
+        let_assert!(HostEvent::Tick { round_id: 1, remaining } = host_ws.recv().await.unwrap());
+        assert!(remaining > 1.5 && remaining <= 2.0);
+        let_assert!(UserEvent::Tick { round_id: 1, .. } = user_ws.recv().await.unwrap());
+        let_assert!(HostEvent::Tick { remaining, .. } = host_ws.recv().await.unwrap());
+        assert!(remaining > 0.5 && remaining <= 1.0);
+
+        // The last tick can race the end of the round
+        loop {
+            match host_ws.recv().await.unwrap() {
+                HostEvent::Tick { remaining, .. } => assert!(remaining < 0.5),
+                event => {
+                    let_assert!(HostEvent::RoundEnd { .. } = event);
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Tests that players can read the question before answers open.
+    #[tokio::test]
+    async fn lead_in() {
+        let server = TestServer::new().await;
+
+        let question = question! {
+            "Fish?", time: 30 => [
+                false => "foo",
+                true => "bar",
+            ]
+        };
+        let settings = RoomSettings {
+            lead_in: 1,
+            ..RoomSettings::default()
+        };
+
+        let (mut host_ws, room_id) = server.create_room_with(vec![question], settings).await;
+        let mut user_ws = server.join_room(room_id, String::from("Johnny")).await;
+        let_assert!(HostEvent::UserJoined { .. } = host_ws.recv().await.unwrap());
+        let_assert!(UserEvent::Joined { .. } = user_ws.recv().await.unwrap());
+
+        host_ws.send(&Action::BeginRound).await;
+        let_assert!(HostEvent::GetReady { question, round_id: 1, .. } = host_ws.recv().await.unwrap());
+        assert_eq!(question, "Fish?");
+        let_assert!(UserEvent::GetReady { question, round_id: 1, .. } = user_ws.recv().await.unwrap());
+        assert_eq!(question, "Fish?");
+
+        // Answering now is too early
+        user_ws.send(&Action::Answer { round_id: 1, answer: Submission::Choice { choice: 1 } }).await;
+        assert_eq!(user_ws.recv().await.unwrap(), UserEvent::AnswerRejected { reason: RejectReason::NotOpen });
+
+        let_assert!(HostEvent::RoundBegin { timing, .. } = host_ws.recv().await.unwrap());
+        assert_eq!(timing.round_id, 1);
+        let_assert!(UserEvent::RoundBegin { question, .. } = user_ws.recv().await.unwrap());
+        assert_eq!(question, "Fish?");
+
+        user_ws.send(&Action::Answer { round_id: 1, answer: Submission::Choice { choice: 1 } }).await;
+        let_assert!(HostEvent::UserAnswered { .. } = host_ws.recv().await.unwrap());
+        let_assert!(HostEvent::RoundEnd { point_gains, .. } = host_ws.recv().await.unwrap());
+        assert!(point_gains.contains_key("Johnny"));
+    }
+
+    /// Tests that the host sees typed answers and that they are normalized.
+    #[tokio::test]
+    async fn type_answer() {
+        let server = TestServer::new().await;
+
+        let question = Question {
+            question: String::from("Capital of France?"),
+            media: None,
+            time: 30,
+            points: PointMultiplier::Standard,
+            kind: QuestionKind::TypeAnswer {
+                accepted: vec![String::from("Paris")],
+                normalization: Normalization::default(),
+            },
+        };
+
+        let (mut host_ws, room_id) = server.create_room(vec![question]).await;
+
+        let host_task = tokio::spawn(async move {
+            let_assert!(HostEvent::UserJoined { .. } = host_ws.recv().await.unwrap());
+            host_ws.send(&Action::BeginRound).await;
+
+            let_assert!(HostEvent::RoundBegin { .. } = host_ws.recv().await.unwrap());
+
+            // Host gets the raw text
+            let_assert!(HostEvent::UserAnswered { text: Some(text), .. } = host_ws.recv().await.unwrap());
+            assert_eq!(text, " PARIS ");
+
+            let_assert!(HostEvent::RoundEnd { point_gains, .. } = host_ws.recv().await.unwrap());
+            assert_quick(point_gains["Johnny"], 1000);
+        });
+
+        let mut user_ws = server.join_room(room_id, String::from("Johnny")).await;
+        let user_task = tokio::spawn(async move {
+            let_assert!(UserEvent::Joined { .. } = user_ws.recv().await.unwrap());
+
+            let_assert!(UserEvent::RoundBegin { prompt: Prompt::TypeAnswer, .. } = user_ws.recv().await.unwrap());
+
+            user_ws.send(&Action::Answer { round_id: 1, answer: Submission::Text { text: String::from(" PARIS ") } }).await;
+            assert_eq!(user_ws.recv().await.unwrap(), UserEvent::AnswerAccepted);
+
+            let_assert!(UserEvent::RoundEnd { point_gain: Some(point_gain), .. } = user_ws.recv().await.unwrap());
+            assert_quick(point_gain, 1000);
+        });
+
+        tokio::try_join!(host_task, user_task).unwrap();
+    }
+
+    /// Tests that polls award no points and tally votes.
+    #[tokio::test]
+    async fn poll() {
+        let server = TestServer::new().await;
+
+        let question = Question {
+            question: String::from("Best fish?"),
+            media: None,
+            time: 30,
+            points: PointMultiplier::Standard,
+            kind: QuestionKind::Poll {
+                choices: vec!["salmon".into(), "tuna".into()],
+            },
+        };
+
+        let (mut host_ws, room_id) = server.create_room(vec![question]).await;
+
+        let host_task = tokio::spawn(async move {
+            let_assert!(HostEvent::UserJoined { .. } = host_ws.recv().await.unwrap());
+            let_assert!(HostEvent::UserJoined { .. } = host_ws.recv().await.unwrap());
+            host_ws.send(&Action::BeginRound).await;
+
+            let_assert!(HostEvent::RoundBegin { .. } = host_ws.recv().await.unwrap());
+
+            // Live tally
+            let_assert!(HostEvent::UserAnswered { choice_counts: Some(counts), .. } = host_ws.recv().await.unwrap());
+            assert_eq!(counts.iter().sum::<u32>(), 1);
+            let_assert!(HostEvent::UserAnswered { choice_counts: Some(counts), .. } = host_ws.recv().await.unwrap());
+            assert_eq!(counts, vec![0, 2]);
+
+            // Final tally
+            let_assert!(HostEvent::RoundEnd { point_gains, choice_counts, .. } = host_ws.recv().await.unwrap());
+            assert!(point_gains.is_empty());
+            assert_eq!(choice_counts, Some(vec![0, 2]));
+        });
+
+        let mut users = Vec::new();
+        for name in ["Alice", "Bob"] {
+            let mut user_ws = server.join_room(room_id, String::from(name)).await;
+            users.push(tokio::spawn(async move {
+                let_assert!(UserEvent::Joined { .. } = user_ws.recv().await.unwrap());
+
+                let_assert!(UserEvent::RoundBegin { prompt: Prompt::Poll { .. }, .. } = user_ws.recv().await.unwrap());
+                user_ws.send(&Action::Answer { round_id: 1, answer: Submission::Choice { choice: 1 } }).await;
+                assert_eq!(user_ws.recv().await.unwrap(), UserEvent::AnswerAccepted);
+
+                let_assert!(UserEvent::RoundEnd { point_gain: None, .. } = user_ws.recv().await.unwrap());
+            }));
+        }
+
+        host_task.await.unwrap();
+        for user in users {
+            user.await.unwrap();
+        }
+    }
+
+    /// Tests that word cloud entries are counted and sent to the host.
+    #[tokio::test]
+    async fn word_cloud() {
+        let server = TestServer::new().await;
+
+        let question = Question {
+            question: String::from("Describe fish"),
+            media: None,
+            time: 30,
+            points: PointMultiplier::Standard,
+            kind: QuestionKind::WordCloud { max_length: 25 },
+        };
+
+        let (mut host_ws, room_id) = server.create_room(vec![question]).await;
+
+        let host_task = tokio::spawn(async move {
+            let_assert!(HostEvent::UserJoined { .. } = host_ws.recv().await.unwrap());
+            let_assert!(HostEvent::UserJoined { .. } = host_ws.recv().await.unwrap());
+            host_ws.send(&Action::BeginRound).await;
+
+            let_assert!(HostEvent::RoundBegin { .. } = host_ws.recv().await.unwrap());
+
+            // Both entries normalize to the same word
+            let mut counts = Vec::new();
+            while counts.len() < 2 {
+                match host_ws.recv().await.unwrap() {
+                    HostEvent::WordCounted { word, count } => {
+                        assert_eq!(word, "wet");
+                        counts.push(count);
+                    }
+                    HostEvent::UserAnswered { .. } => (),
+                    event => panic!("Unexpected event {event:?}"),
+                }
+            }
+            assert_eq!(counts, vec![1, 2]);
+
+            let_assert!(HostEvent::RoundEnd { word_counts: Some(word_counts), .. } = host_ws.recv().await.unwrap());
+            assert_eq!(word_counts, HashMap::from([(String::from("wet"), 2)]));
+        });
+
+        let mut users = Vec::new();
+        for (name, entry) in [("Alice", "Wet!"), ("Bob", " wet")] {
+            let mut user_ws = server.join_room(room_id, String::from(name)).await;
+            users.push(tokio::spawn(async move {
+                let_assert!(UserEvent::Joined { .. } = user_ws.recv().await.unwrap());
+
+                let_assert!(UserEvent::RoundBegin { prompt: Prompt::WordCloud { .. }, .. } = user_ws.recv().await.unwrap());
+                user_ws.send(&Action::Answer { round_id: 1, answer: Submission::Text { text: String::from(entry) } }).await;
+                assert_eq!(user_ws.recv().await.unwrap(), UserEvent::AnswerAccepted);
+
+                let_assert!(UserEvent::RoundEnd { point_gain: None, .. } = user_ws.recv().await.unwrap());
+            }));
+        }
+
+        host_task.await.unwrap();
+        for user in users {
+            user.await.unwrap();
+        }
+    }
+
+    /// Tests that media is forwarded to players only when meant for them.
+    #[tokio::test]
+    async fn media() {
+        let server = TestServer::new().await;
+
+        let image = |url: &str, for_players| Media {
+            source: MediaSource::Image { url: String::from(url) },
+            alt_text: Some(String::from("A fish")),
+            for_players,
+        };
+
+        let question = Question {
+            question: String::from("Which fish is this?"),
+            media: Some(image("https://example.com/fish.png", true)),
+            time: 30,
+            points: PointMultiplier::Standard,
+            kind: QuestionKind::MultipleChoice {
+                choices: vec![
+                    Choice { text: String::from("salmon"), media: Some(image("https://example.com/salmon.png", false)) },
+                    "tuna".into(),
+                ],
+                answer: 0,
+            },
+        };
+
+        let (mut host_ws, room_id) = server.create_room(vec![question.clone()]).await;
+
+        let host_task = tokio::spawn(async move {
+            let_assert!(HostEvent::UserJoined { .. } = host_ws.recv().await.unwrap());
+            host_ws.send(&Action::BeginRound).await;
+
+            // Host gets all of the media
+            let_assert!(HostEvent::RoundBegin { question: begun, .. } = host_ws.recv().await.unwrap());
+            assert_eq!(begun, question);
+        });
+
+        let mut user_ws = server.join_room(room_id, String::from("Johnny")).await;
+        let user_task = tokio::spawn(async move {
+            let_assert!(UserEvent::Joined { .. } = user_ws.recv().await.unwrap());
+
+            // Player only gets media meant for players
+            let_assert!(UserEvent::RoundBegin { prompt: Prompt::MultipleChoice { choices }, media, .. } = user_ws.recv().await.unwrap());
+            assert_eq!(media, Some(image("https://example.com/fish.png", true)));
+            assert_eq!(choices[0].media, None);
+        });
+
+        tokio::try_join!(host_task, user_task).unwrap();
+
+        // Invalid media is refused
+        let mut question = question! {
+            "Fish?", time: 30 => [
+                true => "foo",
+                false => "bar",
+            ]
+        };
+        question.media = Some(image("ftp://example.com/fish.png", false));
+
+        let mut ws = server.connect().await;
+        ws.send(serial(&Action::CreateRoom {
+            questions: vec![question],
+            settings: RoomSettings::default(),
+        })).await.unwrap();
+        let mut host_ws = HostSocket(ws);
+
+        let_assert!(HostEvent::CreateFailed { problems, .. } = host_ws.recv().await.unwrap());
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].question, Some(0));
+        assert_eq!(problems[0].field, "media.url");
+    }
+
+    /// Tests that bad messages are answered with error codes.
+    #[tokio::test]
+    async fn protocol_errors() {
+        let server = TestServer::new().await;
+
+        // Bad first message
+        let mut ws = UserSocket(server.connect().await);
+        ws.0.send(Message::text("{")).await.unwrap();
+        let_assert!(UserEvent::Error(error) = ws.recv().await.unwrap());
+        assert_eq!(error.code, ErrorCode::MalformedJson);
+
+        let (mut host_ws, room_id) = server.create_room(vec![
+            question! {
+                "Fish?", time: 30 => [
+                    true => "foo",
+                    false => "bar",
+                ]
+            }
+        ]).await;
+
+        // Can't start without players
+        host_ws.send(&Action::BeginRound).await;
+        let_assert!(HostEvent::Error(error) = host_ws.recv().await.unwrap());
+        assert_eq!(error.code, ErrorCode::UnexpectedAction);
+
+        let mut user_ws = server.join_room(room_id, String::from("Johnny")).await;
+        let_assert!(UserEvent::Joined { .. } = user_ws.recv().await.unwrap());
+
+        let cases = [
+            (r#"{ "type": "dance" }"#, ErrorCode::UnknownAction),
+            (r#"{ "type": "answer", "choice": "foo" }"#, ErrorCode::InvalidAction),
+            // Unknown variants inside known actions are invalid, not unknown
+            (r#"{ "type": "replayQuestion", "index": 0, "points": "bogus" }"#, ErrorCode::InvalidAction),
+            (
+                r#"{ "type": "createRoom", "questions": [{ "question": "Fish?", "kind": "essay", "time": 30 }] }"#,
+                ErrorCode::InvalidAction,
+            ),
+            (r#"{ "type": "beginRound" }"#, ErrorCode::UnexpectedAction),
+            ("not json", ErrorCode::MalformedJson),
+        ];
+        for (text, code) in cases {
+            user_ws.0.send(Message::text(text)).await.unwrap();
+            let_assert!(UserEvent::Error(error) = user_ws.recv().await.unwrap());
+            assert_eq!(error.code, code, "{text}");
+        }
+    }
+
     #[tokio::test]
     async fn join_leave() {
         let server = TestServer::new().await;
@@ -695,7 +2216,7 @@ mod tests {
             let mut i = 0;
             while let Some(event) = host_ws.recv().await {
                 match event {
-                    HostEvent::UserJoined { username } => {
+                    HostEvent::UserJoined { username, .. } => {
                         assert!(joined.insert(username.clone()), "{username} joined twice");
                     }
                     HostEvent::UserLeft { username } => {
@@ -781,6 +2302,360 @@ mod tests {
         assert_eq!(reason, "Duplicate user");
     }
 
+    #[tokio::test]
+    async fn kick_ban_lock() {
+        let server = TestServer::new().await;
+        let settings = RoomSettings { reconnect_grace: 30, ..RoomSettings::default() };
+        let (mut host, room_id) = server.create_room_with(vec![
+            question! {
+                "Fish?", time: 30 => [
+                    false => "foo",
+                    true => "bar",
+                ]
+            }
+        ], settings).await;
+
+        let mut alice = server.join_room(room_id, String::from("Alice")).await;
+        let_assert!(Some(UserEvent::Joined { .. }) = alice.recv().await);
+        let mut troll = server.join_room(room_id, String::from("Troll")).await;
+        let_assert!(Some(UserEvent::Joined { .. }) = troll.recv().await);
+        let_assert!(Some(HostEvent::UserJoined { .. }) = host.recv().await);
+        let_assert!(Some(HostEvent::UserJoined { .. }) = host.recv().await);
+
+        // Kicking with a ban closes the player's connection, and names are
+        // matched the same way as bans
+        host.send(&Action::KickPlayer { username: String::from("troll"), ban: true }).await;
+        let_assert!(Some(HostEvent::PlayerKicked { username, banned: true }) = host.recv().await);
+        assert_eq!(username, "Troll");
+        let_assert!(Some(UserEvent::Kicked { banned: true }) = troll.recv().await);
+        assert!(troll.recv().await.is_none());
+        let_assert!(Some(HostEvent::UserLeft { username }) = host.recv().await);
+        assert_eq!(username, "Troll");
+
+        // Banned names can't get back in, even written differently
+        let mut troll = server.join_room(room_id, String::from(" TROLL")).await;
+        let_assert!(Some(UserEvent::JoinFailed { reason }) = troll.recv().await);
+        assert_eq!(reason, "Banned from room");
+
+        // Only players in the room can be kicked without a ban
+        host.send(&Action::KickPlayer { username: String::from("Nobody"), ban: false }).await;
+        let_assert!(Some(HostEvent::Error(error)) = host.recv().await);
+        assert_eq!(error.code, ErrorCode::InvalidAction);
+
+        // Lifting the ban lets the name join again
+        host.send(&Action::Unban { username: String::from("troll") }).await;
+        let_assert!(Some(HostEvent::Unbanned { .. }) = host.recv().await);
+        let mut troll = server.join_room(room_id, String::from("Troll")).await;
+        let_assert!(Some(UserEvent::Joined { .. }) = troll.recv().await);
+        let_assert!(Some(HostEvent::UserJoined { .. }) = host.recv().await);
+
+        // Names can't be taken twice by changing case or spaces
+        let mut copycat = server.join_room(room_id, String::from(" alice")).await;
+        let_assert!(Some(UserEvent::JoinFailed { reason }) = copycat.recv().await);
+        assert_eq!(reason, "Duplicate user");
+
+        // Disconnected players are removed as soon as they're kicked
+        let carol = server.join_room(room_id, String::from("Carol")).await;
+        let_assert!(Some(HostEvent::UserJoined { .. }) = host.recv().await);
+        carol.leave().await;
+        let_assert!(Some(HostEvent::UserDisconnected { .. }) = host.recv().await);
+        host.send(&Action::KickPlayer { username: String::from("Carol"), ban: false }).await;
+        let_assert!(Some(HostEvent::PlayerKicked { .. }) = host.recv().await);
+        let_assert!(Some(HostEvent::UserLeft { username }) = host.recv().await);
+        assert_eq!(username, "Carol");
+        let mut carol = server.join_room(room_id, String::from("Carol")).await;
+        let_assert!(Some(UserEvent::Joined { .. }) = carol.recv().await);
+        let_assert!(Some(HostEvent::UserJoined { .. }) = host.recv().await);
+
+        // Nobody new can join a locked room
+        host.send(&Action::LockRoom).await;
+        let_assert!(Some(HostEvent::LockChanged { locked: true }) = host.recv().await);
+        let mut bob = server.join_room(room_id, String::from("Bob")).await;
+        let_assert!(Some(UserEvent::JoinFailed { reason }) = bob.recv().await);
+        assert_eq!(reason, "Room is locked");
+
+        host.send(&Action::UnlockRoom).await;
+        let_assert!(Some(HostEvent::LockChanged { locked: false }) = host.recv().await);
+        let mut bob = server.join_room(room_id, String::from("Bob")).await;
+        let_assert!(Some(UserEvent::Joined { .. }) = bob.recv().await);
+        let_assert!(Some(HostEvent::UserJoined { .. }) = host.recv().await);
+
+        // Players can be kicked during a round too
+        host.send(&Action::BeginRound).await;
+        let_assert!(Some(HostEvent::RoundBegin { .. }) = host.recv().await);
+        let_assert!(Some(UserEvent::RoundBegin { .. }) = bob.recv().await);
+        host.send(&Action::KickPlayer { username: String::from("Bob"), ban: false }).await;
+        let_assert!(Some(HostEvent::PlayerKicked { banned: false, .. }) = host.recv().await);
+        let_assert!(Some(UserEvent::Kicked { banned: false }) = bob.recv().await);
+        assert!(bob.recv().await.is_none());
+
+        // Alice is still playing
+        let_assert!(Some(UserEvent::RoundBegin { .. }) = alice.recv().await);
+    }
+
+    #[tokio::test]
+    async fn late_join() {
+        let server = TestServer::new().await;
+        let questions = vec![
+            question! {
+                "Fish?", time: 30 => [
+                    false => "foo",
+                    true => "bar",
+                ]
+            },
+            question! {
+                "Cat?", time: 30 => [
+                    false => "foo",
+                    true => "bar",
+                ]
+            },
+        ];
+        let (mut host, room_id) = server.create_room(questions.clone()).await;
+
+        let mut alice = server.join_room(room_id, String::from("Alice")).await;
+        let_assert!(Some(UserEvent::Joined { .. }) = alice.recv().await);
+        let_assert!(Some(HostEvent::UserJoined { .. }) = host.recv().await);
+
+        host.send(&Action::BeginRound).await;
+        let_assert!(Some(HostEvent::RoundBegin { timing, .. }) = host.recv().await);
+        let_assert!(Some(UserEvent::RoundBegin { .. }) = alice.recv().await);
+
+        host.send(&Action::Pause).await;
+        let_assert!(Some(HostEvent::RoundPaused { remaining }) = host.recv().await);
+
+        // Players joining mid-round are sent the open question straight away
+        let mut bob = server.join_room(room_id, String::from("Bob")).await;
+        let_assert!(Some(UserEvent::Joined { .. }) = bob.recv().await);
+        let_assert!(Some(UserEvent::Snapshot { phase, score: 0, rank: None }) = bob.recv().await);
+        let_assert!(GamePhase::Round { question, prompt, timing: late_timing, remaining: late_remaining, paused: true, .. } = phase);
+        assert_eq!(question, "Fish?");
+        assert!(matches!(prompt, Prompt::MultipleChoice { .. }));
+        assert_eq!(late_timing.round_id, timing.round_id);
+        assert_eq!(late_remaining, remaining);
+        let_assert!(Some(HostEvent::UserJoined { .. }) = host.recv().await);
+
+        // They can answer the round they joined
+        host.send(&Action::Resume).await;
+        let_assert!(Some(HostEvent::RoundResumed { .. }) = host.recv().await);
+        let_assert!(Some(UserEvent::RoundResumed { .. }) = bob.recv().await);
+        bob.send(&Action::Answer { round_id: timing.round_id, answer: Submission::Choice { choice: 1 } }).await;
+        let_assert!(Some(UserEvent::AnswerAccepted) = bob.recv().await);
+        let_assert!(Some(HostEvent::UserAnswered { .. }) = host.recv().await);
+
+        // Rooms can keep players out until the round ends
+        let settings = RoomSettings { late_join: LateJoin::BetweenRounds, ..RoomSettings::default() };
+        let (mut host, room_id) = server.create_room_with(questions.clone(), settings).await;
+        let mut alice = server.join_room(room_id, String::from("Alice")).await;
+        let_assert!(Some(UserEvent::Joined { .. }) = alice.recv().await);
+        let_assert!(Some(HostEvent::UserJoined { .. }) = host.recv().await);
+
+        host.send(&Action::BeginRound).await;
+        let_assert!(Some(HostEvent::RoundBegin { .. }) = host.recv().await);
+        let mut bob = server.join_room(room_id, String::from("Bob")).await;
+        let_assert!(Some(UserEvent::JoinFailed { reason }) = bob.recv().await);
+        assert_eq!(reason, "Round is in progress");
+
+        host.send(&Action::EndRound).await;
+        let_assert!(Some(HostEvent::RoundEnd { .. }) = host.recv().await);
+        let mut bob = server.join_room(room_id, String::from("Bob")).await;
+        let_assert!(Some(UserEvent::Joined { .. }) = bob.recv().await);
+        let_assert!(Some(UserEvent::Snapshot { phase: GamePhase::BetweenRounds, score: 0, rank: None }) = bob.recv().await);
+
+        // Or until the game is over
+        let settings = RoomSettings { late_join: LateJoin::LobbyOnly, ..RoomSettings::default() };
+        let (mut host, room_id) = server.create_room_with(questions, settings).await;
+        let mut alice = server.join_room(room_id, String::from("Alice")).await;
+        let_assert!(Some(UserEvent::Joined { .. }) = alice.recv().await);
+        let_assert!(Some(HostEvent::UserJoined { .. }) = host.recv().await);
+
+        host.send(&Action::BeginRound).await;
+        let_assert!(Some(HostEvent::RoundBegin { .. }) = host.recv().await);
+        let mut bob = server.join_room(room_id, String::from("Bob")).await;
+        let_assert!(Some(UserEvent::JoinFailed { reason }) = bob.recv().await);
+        assert_eq!(reason, "Game has already started");
+    }
+
+    #[tokio::test]
+    async fn reconnect() {
+        let server = TestServer::new().await;
+        let questions = vec![
+            question! {
+                "Fish?", time: 30 => [
+                    false => "foo",
+                    true => "bar",
+                ]
+            },
+            question! {
+                "Cat?", time: 30 => [
+                    false => "foo",
+                    true => "bar",
+                ]
+            },
+        ];
+        let settings = RoomSettings { reconnect_grace: 30, ..RoomSettings::default() };
+        let (mut host, room_id) = server.create_room_with(questions.clone(), settings).await;
+
+        let mut alice = server.join_room(room_id, String::from("Alice")).await;
+        let_assert!(Some(UserEvent::Joined { resume_token }) = alice.recv().await);
+        let_assert!(Some(HostEvent::UserJoined { .. }) = host.recv().await);
+        let mut bob = server.join_room(room_id, String::from("Bob")).await;
+        let_assert!(Some(UserEvent::Joined { .. }) = bob.recv().await);
+        let_assert!(Some(HostEvent::UserJoined { .. }) = host.recv().await);
+
+        host.send(&Action::BeginRound).await;
+        let_assert!(Some(HostEvent::RoundBegin { .. }) = host.recv().await);
+        let_assert!(Some(UserEvent::RoundBegin { .. }) = alice.recv().await);
+        alice.send(&Action::Answer { round_id: 1, answer: Submission::Choice { choice: 1 } }).await;
+        let_assert!(Some(UserEvent::AnswerAccepted) = alice.recv().await);
+        let_assert!(Some(HostEvent::UserAnswered { .. }) = host.recv().await);
+
+        // Losing the connection keeps the player's place
+        alice.leave().await;
+        let_assert!(Some(HostEvent::UserDisconnected { username }) = host.recv().await);
+        assert_eq!(username, "Alice");
+
+        // Rejoining restores the round, including the answer already given
+        let mut alice = server.rejoin(room_id, resume_token.clone()).await;
+        let_assert!(Some(UserEvent::Joined { resume_token: same_token }) = alice.recv().await);
+        assert_eq!(same_token, resume_token);
+        let_assert!(Some(UserEvent::Snapshot { phase, score: 0, rank: None }) = alice.recv().await);
+        let_assert!(GamePhase::Round { answered: true, paused: false, .. } = phase);
+        let_assert!(Some(HostEvent::UserRejoined { username }) = host.recv().await);
+        assert_eq!(username, "Alice");
+
+        alice.send(&Action::Answer { round_id: 1, answer: Submission::Choice { choice: 0 } }).await;
+        let_assert!(Some(UserEvent::AnswerRejected { reason: RejectReason::AlreadyAnswered }) = alice.recv().await);
+
+        host.send(&Action::EndRound).await;
+        let_assert!(Some(HostEvent::RoundEnd { point_gains, .. }) = host.recv().await);
+        let points = point_gains["Alice"];
+        let_assert!(Some(UserEvent::RoundEnd { score, .. }) = alice.recv().await);
+        assert_eq!(score, points);
+
+        // The score is kept between rounds too
+        alice.leave().await;
+        let_assert!(Some(HostEvent::Leaderboard { .. }) = host.recv().await);
+        let_assert!(Some(HostEvent::UserDisconnected { .. }) = host.recv().await);
+        let mut alice = server.rejoin(room_id, resume_token.clone()).await;
+        let_assert!(Some(UserEvent::Joined { .. }) = alice.recv().await);
+        let_assert!(Some(UserEvent::Snapshot { phase: GamePhase::BetweenRounds, score, rank: Some(1) }) = alice.recv().await);
+        assert_eq!(score, points);
+        let_assert!(Some(HostEvent::UserRejoined { .. }) = host.recv().await);
+
+        // Rejoining from somewhere else closes the old connection
+        let mut new_alice = server.rejoin(room_id, resume_token).await;
+        let_assert!(Some(UserEvent::Joined { .. }) = new_alice.recv().await);
+        let_assert!(Some(UserEvent::Snapshot { .. }) = new_alice.recv().await);
+        let_assert!(Some(HostEvent::UserRejoined { .. }) = host.recv().await);
+        let_assert!(Some(UserEvent::Replaced) = alice.recv().await);
+        assert_eq!(alice.recv().await, None);
+
+        // The new connection is still in the room
+        host.send(&Action::BeginRound).await;
+        let_assert!(Some(HostEvent::RoundBegin { .. }) = host.recv().await);
+        let_assert!(Some(UserEvent::RoundBegin { .. }) = new_alice.recv().await);
+
+        let mut eve = server.rejoin(room_id, String::from("guess")).await;
+        let_assert!(Some(UserEvent::JoinFailed { reason }) = eve.recv().await);
+        assert_eq!(reason, "Unknown resume token");
+
+        // Players who don't come back in time leave for good
+        let settings = RoomSettings { reconnect_grace: 1, ..RoomSettings::default() };
+        let (mut host, room_id) = server.create_room_with(questions, settings).await;
+        let mut alice = server.join_room(room_id, String::from("Alice")).await;
+        let_assert!(Some(UserEvent::Joined { resume_token }) = alice.recv().await);
+        let_assert!(Some(HostEvent::UserJoined { .. }) = host.recv().await);
+
+        alice.leave().await;
+        let_assert!(Some(HostEvent::UserDisconnected { .. }) = host.recv().await);
+        let_assert!(Some(HostEvent::UserLeft { username }) = host.recv().await);
+        assert_eq!(username, "Alice");
+
+        let mut alice = server.rejoin(room_id, resume_token).await;
+        let_assert!(Some(UserEvent::JoinFailed { reason }) = alice.recv().await);
+        assert_eq!(reason, "Unknown resume token");
+    }
+
+    #[tokio::test]
+    async fn disconnected_players() {
+        let server = TestServer::new().await;
+        let questions = vec![question! {
+            "Fish?", time: 30 => [
+                false => "foo",
+                true => "bar",
+            ]
+        }];
+        let settings = RoomSettings { reconnect_grace: 30, ..RoomSettings::default() };
+        let (mut host, room_id) = server.create_room_with(questions, settings).await;
+
+        // A game can't start with only disconnected players
+        let mut alice = server.join_room(room_id, String::from("Alice")).await;
+        let_assert!(Some(UserEvent::Joined { resume_token }) = alice.recv().await);
+        let_assert!(Some(HostEvent::UserJoined { .. }) = host.recv().await);
+        alice.leave().await;
+        let_assert!(Some(HostEvent::UserDisconnected { .. }) = host.recv().await);
+
+        host.send(&Action::BeginRound).await;
+        let_assert!(Some(HostEvent::Error(_)) = host.recv().await);
+
+        let mut alice = server.rejoin(room_id, resume_token).await;
+        let_assert!(Some(UserEvent::Joined { .. }) = alice.recv().await);
+        let_assert!(Some(HostEvent::UserRejoined { .. }) = host.recv().await);
+        let bob = server.join_room(room_id, String::from("Bob")).await;
+        let_assert!(Some(HostEvent::UserJoined { .. }) = host.recv().await);
+        bob.leave().await;
+        let_assert!(Some(HostEvent::UserDisconnected { .. }) = host.recv().await);
+
+        // The round ends once every connected player has answered
+        host.send(&Action::BeginRound).await;
+        let_assert!(Some(HostEvent::RoundBegin { .. }) = host.recv().await);
+        let_assert!(Some(UserEvent::RoundBegin { .. }) = alice.recv().await);
+        alice.send(&Action::Answer { round_id: 1, answer: Submission::Choice { choice: 1 } }).await;
+        let_assert!(Some(UserEvent::AnswerAccepted) = alice.recv().await);
+        let_assert!(Some(HostEvent::UserAnswered { .. }) = host.recv().await);
+        let_assert!(Some(HostEvent::RoundEnd { .. }) = host.recv().await);
+    }
+
+    #[tokio::test]
+    async fn kicked_player_grace() {
+        let server = TestServer::new().await;
+        let questions = vec![question! {
+            "Fish?", time: 30 => [
+                false => "foo",
+                true => "bar",
+            ]
+        }];
+        let settings = RoomSettings { reconnect_grace: 1, ..RoomSettings::default() };
+        let (mut host, room_id) = server.create_room_with(questions, settings).await;
+
+        let carol = server.join_room(room_id, String::from("Carol")).await;
+        let_assert!(Some(HostEvent::UserJoined { .. }) = host.recv().await);
+        carol.leave().await;
+        let_assert!(Some(HostEvent::UserDisconnected { .. }) = host.recv().await);
+        host.send(&Action::KickPlayer { username: String::from("Carol"), ban: false }).await;
+        let_assert!(Some(HostEvent::PlayerKicked { .. }) = host.recv().await);
+        let_assert!(Some(HostEvent::UserLeft { .. }) = host.recv().await);
+
+        let mut carol = server.join_room(room_id, String::from("Carol")).await;
+        let_assert!(Some(UserEvent::Joined { .. }) = carol.recv().await);
+        let_assert!(Some(HostEvent::UserJoined { .. }) = host.recv().await);
+
+        // The kicked player's grace period ending doesn't remove the new one
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        host.send(&Action::BeginRound).await;
+        let_assert!(Some(HostEvent::RoundBegin { .. }) = host.recv().await);
+        let_assert!(Some(UserEvent::RoundBegin { .. }) = carol.recv().await);
+    }
+
+    /// Asserts that points are what an instant answer would earn, give or take
+    /// a little for the time the answer spent in transit.
+    fn assert_quick(points: u32, full_points: u32) {
+        assert!(
+            points <= full_points && points >= full_points * 98 / 100,
+            "{points} isn't close to {full_points}"
+        );
+    }
+
     /// Convert a `Serialize`able into a JSON message.
     fn serial(s: &impl Serialize) -> Message {
         let json_string = serde_json::to_string(s).unwrap();
@@ -0,0 +1,523 @@
+use super::api::{
+    AnswerOutcome, Choice, MultiSelectScoring, Normalization, OrderingScoring, Prompt, QuestionKind, Solution,
+    Submission,
+};
+
+use std::collections::HashSet;
+
+use rand::seq::SliceRandom;
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// A question which is currently being asked in a round.
+pub struct AskedQuestion {
+    pub kind: QuestionKind,
+    /// The order ordering items are shown to players in, as indices into the
+    /// correctly ordered list. Empty for every other kind of question.
+    shuffle: Vec<usize>,
+}
+
+impl AskedQuestion {
+    /// Prepares a question for a round, shuffling items if needed.
+    pub fn new(kind: QuestionKind) -> Self {
+        let shuffle = match &kind {
+            QuestionKind::Ordering { items, .. } => {
+                let mut shuffle: Vec<usize> = (0..items.len()).collect();
+
+                // Make sure the items aren't shown in the correct order
+                let mut rng = rand::thread_rng();
+                while items.len() > 1 && shuffle.iter().enumerate().all(|(i, &item)| i == item) {
+                    shuffle.shuffle(&mut rng);
+                }
+
+                shuffle
+            }
+            _ => Vec::new(),
+        };
+
+        Self { kind, shuffle }
+    }
+
+    /// Returns the part of the question which players are allowed to see.
+    pub fn prompt(&self) -> Prompt {
+        match &self.kind {
+            QuestionKind::MultipleChoice { choices, .. } => Prompt::MultipleChoice {
+                choices: for_players(choices),
+            },
+            QuestionKind::MultiSelect { choices, .. } => Prompt::MultiSelect {
+                choices: for_players(choices),
+            },
+            QuestionKind::TypeAnswer { .. } => Prompt::TypeAnswer,
+            QuestionKind::Slider { min, max, step, .. } => Prompt::Slider {
+                min: *min,
+                max: *max,
+                step: *step,
+            },
+            QuestionKind::Ordering { items, .. } => Prompt::Ordering {
+                items: self.shuffle.iter().map(|&i| items[i].clone()).collect(),
+            },
+            QuestionKind::Poll { choices } => Prompt::Poll {
+                choices: for_players(choices),
+            },
+            QuestionKind::WordCloud { max_length } => Prompt::WordCloud {
+                max_length: *max_length,
+            },
+        }
+    }
+
+    /// Returns whether this question has a correct answer, ie. it isn't a poll
+    /// or a word cloud.
+    pub fn is_graded(&self) -> bool {
+        !matches!(self.kind, QuestionKind::Poll { .. } | QuestionKind::WordCloud { .. })
+    }
+
+    /// Returns whether this question is a word cloud.
+    pub fn is_word_cloud(&self) -> bool {
+        matches!(self.kind, QuestionKind::WordCloud { .. })
+    }
+
+    /// Returns the number of choices if this question is a poll.
+    pub fn poll_choices(&self) -> Option<usize> {
+        match &self.kind {
+            QuestionKind::Poll { choices } => Some(choices.len()),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of choices if players pick from a list of them.
+    pub fn choice_count(&self) -> Option<usize> {
+        match &self.kind {
+            QuestionKind::MultipleChoice { choices, .. }
+            | QuestionKind::MultiSelect { choices, .. }
+            | QuestionKind::Poll { choices } => Some(choices.len()),
+            _ => None,
+        }
+    }
+
+    /// Returns the correct answer, or `None` for polls and word clouds.
+    pub fn solution(&self) -> Option<Solution> {
+        match &self.kind {
+            QuestionKind::MultipleChoice { answer, .. } => Some(Solution::Choice { choice: *answer }),
+            QuestionKind::MultiSelect { answers, .. } => Some(Solution::Choices { choices: answers.clone() }),
+            QuestionKind::TypeAnswer { accepted, .. } => Some(Solution::Accepted { accepted: accepted.clone() }),
+            QuestionKind::Slider { answer, .. } => Some(Solution::Number { value: *answer }),
+            QuestionKind::Ordering { items, .. } => Some(Solution::Items { items: items.clone() }),
+            QuestionKind::Poll { .. } | QuestionKind::WordCloud { .. } => None,
+        }
+    }
+
+    /// Describes how a graded answer went, from the credit it earned.
+    pub fn outcome(&self, credit: f64) -> AnswerOutcome {
+        if !self.is_graded() {
+            AnswerOutcome::Answered
+        } else if credit >= 1.0 {
+            AnswerOutcome::Correct
+        } else if credit > 0.0 {
+            AnswerOutcome::PartlyCorrect
+        } else {
+            AnswerOutcome::Wrong
+        }
+    }
+
+    /// Grades a submission from `0.0` (completely wrong) to `1.0` (completely
+    /// right).
+    ///
+    /// Returns `None` if the submission doesn't fit the question, eg. a set of
+    /// choices for a multiple choice question or an index out of range.
+    ///
+    /// Polls and word clouds have no correct answer, so every entry is graded
+    /// `0.0`.
+    pub fn grade(&self, submission: &Submission) -> Option<f64> {
+        match (&self.kind, submission) {
+            (QuestionKind::MultipleChoice { choices, answer }, Submission::Choice { choice }) => {
+                if *choice >= choices.len() {
+                    return None;
+                }
+
+                Some(if choice == answer { 1.0 } else { 0.0 })
+            }
+            (
+                QuestionKind::MultiSelect { choices, answers, scoring },
+                Submission::Choices { choices: picked },
+            ) => {
+                if picked.iter().any(|&choice| choice >= choices.len()) {
+                    return None;
+                }
+
+                let picked: HashSet<_> = picked.iter().copied().collect();
+                let answers: HashSet<_> = answers.iter().copied().collect();
+
+                let credit = match scoring {
+                    MultiSelectScoring::AllOrNothing => {
+                        if picked == answers { 1.0 } else { 0.0 }
+                    }
+                    // Every correct pick is worth an equal share, and every
+                    // wrong pick cancels out a correct one.
+                    MultiSelectScoring::Partial => {
+                        if answers.is_empty() {
+                            return Some(if picked.is_empty() { 1.0 } else { 0.0 });
+                        }
+
+                        let hits = picked.intersection(&answers).count() as f64;
+                        let misses = picked.difference(&answers).count() as f64;
+
+                        ((hits - misses) / answers.len() as f64).max(0.0)
+                    }
+                };
+
+                Some(credit)
+            }
+            (
+                QuestionKind::TypeAnswer { accepted, normalization },
+                Submission::Text { text },
+            ) => {
+                let text = normalization.apply(text);
+
+                let correct = accepted.iter().any(|answer| {
+                    let answer = normalization.apply(answer);
+                    edit_distance(&text, &answer) <= normalization.max_typos
+                });
+
+                Some(if correct { 1.0 } else { 0.0 })
+            }
+            (
+                QuestionKind::Slider { min, max, step, answer, tolerance },
+                Submission::Number { value },
+            ) => {
+                if !value.is_finite() || value < min || value > max {
+                    return None;
+                }
+
+                // The slider only stops on whole steps from `min`, so other
+                // values didn't come from it. A little slack is left for
+                // floating point error.
+                let steps = (value - min) / step;
+                if (steps - steps.round()).abs() > 1e-6 {
+                    return None;
+                }
+
+                let distance = (value - answer).abs();
+
+                // A tolerance of zero only accepts the exact answer
+                if *tolerance <= 0.0 {
+                    return Some(if distance == 0.0 { 1.0 } else { 0.0 });
+                }
+
+                Some((1.0 - distance / tolerance).max(0.0))
+            }
+            (QuestionKind::Ordering { items, scoring }, Submission::Order { order }) => {
+                // Must be a permutation of the shown items
+                let mut seen = vec![false; items.len()];
+                if order.len() != items.len() {
+                    return None;
+                }
+                for &i in order {
+                    if i >= items.len() || std::mem::replace(&mut seen[i], true) {
+                        return None;
+                    }
+                }
+
+                // Turn the shown positions back into the original positions
+                let in_place = order
+                    .iter()
+                    .enumerate()
+                    .filter(|&(position, &i)| self.shuffle[i] == position)
+                    .count();
+
+                let credit = match scoring {
+                    OrderingScoring::ExactOrder => {
+                        if in_place == items.len() { 1.0 } else { 0.0 }
+                    }
+                    OrderingScoring::PerPosition if items.is_empty() => 1.0,
+                    OrderingScoring::PerPosition => in_place as f64 / items.len() as f64,
+                };
+
+                Some(credit)
+            }
+            (QuestionKind::Poll { choices }, Submission::Choice { choice }) => {
+                if *choice >= choices.len() {
+                    return None;
+                }
+
+                Some(0.0)
+            }
+            (QuestionKind::WordCloud { max_length }, Submission::Text { text }) => {
+                let word = cloud_word(text);
+                if word.is_empty() || word.chars().count() > *max_length {
+                    return None;
+                }
+
+                Some(0.0)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Normalization {
+    /// Cleans up a typed answer so it can be compared with others.
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = if self.ignore_whitespace {
+            text.split_whitespace().collect::<Vec<_>>().join(" ")
+        } else {
+            text.to_owned()
+        };
+
+        if self.ignore_case {
+            text = text.to_lowercase();
+        }
+
+        if self.ignore_diacritics {
+            // Split accented letters into the letter and the accent, then
+            // throw away the accent
+            text = text.nfd().filter(|&c| !is_combining_mark(c)).nfc().collect();
+        }
+
+        text
+    }
+}
+
+/// Adds the choices picked in a submission to the count for each choice.
+///
+/// The submission must have been graded already, so every index is in range.
+pub fn tally(counts: &mut [u32], submission: &Submission) {
+    match submission {
+        Submission::Choice { choice } => counts[*choice] += 1,
+        Submission::Choices { choices } => {
+            for &choice in choices.iter().collect::<HashSet<_>>() {
+                counts[choice] += 1;
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Copies choices, leaving out media which isn't meant for players.
+fn for_players(choices: &[Choice]) -> Vec<Choice> {
+    choices
+        .iter()
+        .map(|choice| Choice {
+            text: choice.text.clone(),
+            media: choice.media.clone().filter(|media| media.for_players),
+        })
+        .collect()
+}
+
+/// Normalizes a word cloud entry so that eg. "Fish!" and " fish" are counted
+/// together.
+pub fn cloud_word(text: &str) -> String {
+    Normalization::default()
+        .apply(text)
+        .trim_matches(|c: char| c.is_ascii_punctuation())
+        .trim()
+        .to_owned()
+}
+
+/// The Levenshtein distance between two strings, ie. the least amount of
+/// inserted, deleted or replaced characters needed to turn one into the other.
+///
+/// Relevant: https://en.wikipedia.org/wiki/Levenshtein_distance
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+
+    // Only the previous row of the table is needed
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, &b_char) in b.iter().enumerate() {
+            let replace = diagonal + usize::from(a_char != b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = replace.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::let_assert;
+
+    use crate::ws::api::{
+        AnswerOutcome, MultiSelectScoring, Normalization, OrderingScoring, Prompt, Question, QuestionKind,
+        Solution, Submission,
+    };
+
+    use super::{cloud_word, edit_distance, tally, AskedQuestion};
+
+    fn multi_select(scoring: MultiSelectScoring) -> AskedQuestion {
+        AskedQuestion::new(QuestionKind::MultiSelect {
+            choices: vec!["a".into(), "b".into(), "c".into(), "d".into()],
+            answers: vec![0, 2],
+            scoring,
+        })
+    }
+
+    #[test]
+    fn missing_kind_is_multiple_choice() {
+        let json = r#"{ "question": "Fish?", "choices": ["foo", "bar"], "answer": 1, "time": 30 }"#;
+        let question: Question = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            question.kind,
+            QuestionKind::MultipleChoice {
+                choices: vec!["foo".into(), "bar".into()],
+                answer: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn multi_select_all_or_nothing() {
+        let kind = multi_select(MultiSelectScoring::AllOrNothing);
+
+        assert_eq!(kind.grade(&Submission::Choices { choices: vec![2, 0] }), Some(1.0));
+        assert_eq!(kind.grade(&Submission::Choices { choices: vec![0] }), Some(0.0));
+        assert_eq!(kind.grade(&Submission::Choices { choices: vec![0, 1, 2] }), Some(0.0));
+        assert_eq!(kind.grade(&Submission::Choices { choices: vec![4] }), None);
+        assert_eq!(kind.grade(&Submission::Choice { choice: 0 }), None);
+    }
+
+    #[test]
+    fn multi_select_reveal() {
+        let question = multi_select(MultiSelectScoring::Partial);
+
+        assert_eq!(question.solution(), Some(Solution::Choices { choices: vec![0, 2] }));
+        assert_eq!(question.outcome(1.0), AnswerOutcome::Correct);
+        assert_eq!(question.outcome(0.5), AnswerOutcome::PartlyCorrect);
+        assert_eq!(question.outcome(0.0), AnswerOutcome::Wrong);
+
+        // Picking a choice twice only counts once
+        let mut counts = vec![0; question.choice_count().unwrap()];
+        tally(&mut counts, &Submission::Choices { choices: vec![0, 1, 1] });
+        tally(&mut counts, &Submission::Choices { choices: vec![1] });
+        assert_eq!(counts, vec![1, 2, 0, 0]);
+    }
+
+    #[test]
+    fn multi_select_partial() {
+        let kind = multi_select(MultiSelectScoring::Partial);
+
+        assert_eq!(kind.grade(&Submission::Choices { choices: vec![0, 2] }), Some(1.0));
+        assert_eq!(kind.grade(&Submission::Choices { choices: vec![0] }), Some(0.5));
+        assert_eq!(kind.grade(&Submission::Choices { choices: vec![0, 0] }), Some(0.5));
+        assert_eq!(kind.grade(&Submission::Choices { choices: vec![0, 1, 2] }), Some(0.5));
+        assert_eq!(kind.grade(&Submission::Choices { choices: vec![1, 3] }), Some(0.0));
+    }
+
+    #[test]
+    fn type_answer_normalization() {
+        let kind = AskedQuestion::new(QuestionKind::TypeAnswer {
+            accepted: vec![String::from("São Paulo"), String::from("SP")],
+            normalization: Normalization::default(),
+        });
+        let text = |text: &str| Submission::Text { text: String::from(text) };
+
+        assert_eq!(kind.grade(&text("  sao   PAULO ")), Some(1.0));
+        assert_eq!(kind.grade(&text("sp")), Some(1.0));
+        assert_eq!(kind.grade(&text("sao paolo")), Some(0.0));
+        assert_eq!(kind.grade(&Submission::Choice { choice: 0 }), None);
+
+        let strict = AskedQuestion::new(QuestionKind::TypeAnswer {
+            accepted: vec![String::from("São Paulo")],
+            normalization: Normalization {
+                ignore_case: false,
+                ignore_diacritics: false,
+                max_typos: 1,
+                ..Normalization::default()
+            },
+        });
+
+        assert_eq!(strict.grade(&text("São Paolo")), Some(1.0));
+        assert_eq!(strict.grade(&text("Sao Paulo")), Some(1.0));
+        assert_eq!(strict.grade(&text("sao Paulo")), Some(0.0));
+    }
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("ação", "acao"), 2);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+
+    #[test]
+    fn slider_tolerance() {
+        let kind = AskedQuestion::new(QuestionKind::Slider {
+            min: 1900.0,
+            max: 2000.0,
+            step: 0.5,
+            answer: 1969.0,
+            tolerance: 10.0,
+        });
+        let number = |value| Submission::Number { value };
+
+        assert_eq!(kind.grade(&number(1969.0)), Some(1.0));
+        assert_eq!(kind.grade(&number(1974.0)), Some(0.5));
+        assert_eq!(kind.grade(&number(1967.5)), Some(0.85));
+        assert_eq!(kind.grade(&number(1950.0)), Some(0.0));
+        assert_eq!(kind.grade(&number(2001.0)), None);
+        // Off-step values are rejected
+        assert_eq!(kind.grade(&number(1969.25)), None);
+    }
+
+    #[test]
+    fn ordering_is_shuffled() {
+        let items: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let asked = AskedQuestion::new(QuestionKind::Ordering {
+            items: items.clone(),
+            scoring: OrderingScoring::ExactOrder,
+        });
+
+        let_assert!(Prompt::Ordering { items: shown } = asked.prompt());
+        assert_ne!(shown, items);
+
+        // Putting the shown items back in order is correct
+        let order: Vec<usize> = items
+            .iter()
+            .map(|item| shown.iter().position(|shown| shown == item).unwrap())
+            .collect();
+        assert_eq!(asked.grade(&Submission::Order { order }), Some(1.0));
+    }
+
+    #[test]
+    fn ordering_scoring() {
+        let kind = |scoring| QuestionKind::Ordering {
+            items: vec![String::from("a"), String::from("b"), String::from("c"), String::from("d")],
+            scoring,
+        };
+        // Shown as "c", "a", "d", "b"
+        let shuffle = vec![2, 0, 3, 1];
+        let order = |order: &[usize]| Submission::Order { order: order.to_vec() };
+
+        let exact = AskedQuestion { kind: kind(OrderingScoring::ExactOrder), shuffle: shuffle.clone() };
+        assert_eq!(exact.grade(&order(&[1, 3, 0, 2])), Some(1.0));
+        assert_eq!(exact.grade(&order(&[1, 3, 2, 0])), Some(0.0));
+
+        let partial = AskedQuestion { kind: kind(OrderingScoring::PerPosition), shuffle };
+        assert_eq!(partial.grade(&order(&[1, 3, 2, 0])), Some(0.5));
+        assert_eq!(partial.grade(&order(&[0, 1, 2, 3])), Some(0.0));
+
+        // Malformed permutations
+        assert_eq!(partial.grade(&order(&[1, 3, 0])), None);
+        assert_eq!(partial.grade(&order(&[1, 1, 0, 2])), None);
+        assert_eq!(partial.grade(&order(&[1, 3, 0, 4])), None);
+    }
+
+    #[test]
+    fn word_cloud_entries() {
+        let asked = AskedQuestion::new(QuestionKind::WordCloud { max_length: 10 });
+        let text = |text: &str| Submission::Text { text: String::from(text) };
+
+        assert_eq!(cloud_word("  Peixe!! "), "peixe");
+        assert_eq!(cloud_word("Água  viva"), "agua viva");
+
+        assert_eq!(asked.grade(&text("Fish!")), Some(0.0));
+        assert_eq!(asked.grade(&text(" ?! ")), None);
+        assert_eq!(asked.grade(&text("a very long entry")), None);
+    }
+}
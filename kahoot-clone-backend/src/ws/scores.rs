@@ -0,0 +1,425 @@
+use super::api::{RoomSettings, Standing, TeamScoring, TeamSettings, TeamStanding};
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+/// Keeps track of every player's results over a game.
+pub struct Scoreboard {
+    players: HashMap<String, PlayerRecord>,
+    /// The results of each question played, so replays can replace them.
+    rounds: HashMap<usize, RoundResults>,
+    /// The number of rounds which had a correct answer, ie. not polls.
+    graded_rounds: u32,
+    /// The bonus for each correct answer in a row after the first.
+    streak_bonus: u32,
+    /// The largest bonus a streak can earn.
+    max_streak_bonus: u32,
+}
+
+/// What everyone earned from a single question.
+struct RoundResults {
+    point_gains: HashMap<String, u32>,
+    correct: HashSet<String>,
+    graded: bool,
+}
+
+/// A single player's results.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PlayerRecord {
+    pub score: u32,
+    /// The number of questions answered completely correctly.
+    pub correct: u32,
+    /// The number of questions answered correctly in a row.
+    pub streak: u32,
+}
+
+impl Scoreboard {
+    pub fn new(settings: &RoomSettings) -> Self {
+        // Some strategies, like `AccuracyOnly`, don't get bonuses
+        let streak_bonus = if settings.scoring.strategy().streak_bonuses() {
+            settings.streak_bonus
+        } else {
+            0
+        };
+
+        Self {
+            players: HashMap::new(),
+            rounds: HashMap::new(),
+            graded_rounds: 0,
+            streak_bonus,
+            max_streak_bonus: settings.max_streak_bonus,
+        }
+    }
+
+    /// Adds the results of a round to each player's record.
+    ///
+    /// `question` is the index of the question asked. If it was asked before,
+    /// the points and correct answers from then are taken away first.
+    /// Streaks aren't rewound, they carry on as normal.
+    ///
+    /// `correct` holds the players who answered completely correctly, and
+    /// `graded` is whether the round had a correct answer at all.
+    ///
+    /// In graded rounds, correct answers extend a player's streak and earn a
+    /// bonus, which is added to `point_gains`. Everyone else in `players`
+    /// loses their streak.
+    pub fn add_round(
+        &mut self,
+        question: usize,
+        players: &HashSet<String>,
+        point_gains: &mut HashMap<String, u32>,
+        correct: &HashSet<String>,
+        graded: bool,
+    ) {
+        if let Some(earlier) = self.rounds.remove(&question) {
+            for (username, gain) in &earlier.point_gains {
+                let record = self.players.entry(username.clone()).or_default();
+                record.score = record.score.saturating_sub(*gain);
+            }
+            for username in &earlier.correct {
+                let record = self.players.entry(username.clone()).or_default();
+                record.correct = record.correct.saturating_sub(1);
+            }
+            if earlier.graded {
+                self.graded_rounds -= 1;
+            }
+        }
+
+        if graded {
+            self.graded_rounds += 1;
+
+            for username in players {
+                let record = self.players.entry(username.clone()).or_default();
+
+                if !correct.contains(username) {
+                    record.streak = 0;
+                    continue;
+                }
+
+                record.correct += 1;
+                record.streak += 1;
+
+                // Only answers which were worth points earn a bonus
+                // Saturating so huge bonuses can't overflow
+                let bonus = self
+                    .streak_bonus
+                    .saturating_mul(record.streak - 1)
+                    .min(self.max_streak_bonus);
+                if let Some(gain) = point_gains.get_mut(username) {
+                    *gain = gain.saturating_add(bonus);
+                }
+            }
+        }
+
+        for (username, gain) in point_gains.iter() {
+            let record = self.players.entry(username.clone()).or_default();
+            record.score = record.score.saturating_add(*gain);
+        }
+
+        self.rounds.insert(question, RoundResults {
+            point_gains: point_gains.clone(),
+            correct: if graded { correct.clone() } else { HashSet::new() },
+            graded,
+        });
+    }
+
+    /// A player's results so far.
+    pub fn record(&self, username: &str) -> PlayerRecord {
+        self.players.get(username).copied().unwrap_or_default()
+    }
+
+    /// The number of rounds so far which had a correct answer.
+    pub fn graded_rounds(&self) -> u32 {
+        self.graded_rounds
+    }
+
+    /// Ranks players from the highest to the lowest total score.
+    ///
+    /// Players with the same score share a rank, and the ranks after them are
+    /// skipped, eg. 1, 2, 2, 4.
+    pub fn rank<'a>(&self, players: impl IntoIterator<Item = &'a String>) -> Vec<Standing> {
+        let mut ranking: Vec<Standing> = players
+            .into_iter()
+            .map(|username| {
+                let record = self.record(username);
+                Standing {
+                    username: username.clone(),
+                    score: record.score,
+                    correct: record.correct,
+                    streak: record.streak,
+                    rank: 0,
+                }
+            })
+            .collect();
+
+        // Highest score first, ties are sorted by name so the order is stable
+        ranking.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.username.cmp(&b.username)));
+
+        for i in 0..ranking.len() {
+            ranking[i].rank = if i > 0 && ranking[i].score == ranking[i - 1].score {
+                ranking[i - 1].rank
+            } else {
+                i + 1
+            };
+        }
+
+        ranking
+    }
+}
+
+/// Ranks teams from the highest to the lowest score.
+///
+/// `members` maps each player to their team. Teams are scored from their
+/// members' standings in `ranking`, and ranked the same way players are.
+pub fn rank_teams(ranking: &[Standing], teams: &TeamSettings, members: &HashMap<String, String>) -> Vec<TeamStanding> {
+    let mut team_ranking: Vec<TeamStanding> = teams
+        .names
+        .iter()
+        .map(|team| {
+            let scores: Vec<u32> = ranking
+                .iter()
+                .filter(|standing| members.get(&standing.username) == Some(team))
+                .map(|standing| standing.score)
+                .collect();
+
+            // Summed as `u64` so big teams can't overflow
+            let total: u64 = scores.iter().map(|&score| score as u64).sum();
+            let score = match teams.scoring {
+                TeamScoring::Sum => total.min(u32::MAX as u64) as u32,
+                TeamScoring::Average if scores.is_empty() => 0,
+                TeamScoring::Average => (total / scores.len() as u64) as u32,
+                TeamScoring::Best => scores.iter().copied().max().unwrap_or(0),
+            };
+
+            TeamStanding {
+                team: team.clone(),
+                score,
+                members: scores.len(),
+                rank: 0,
+            }
+        })
+        .collect();
+
+    // Teams are kept in the host's order when tied
+    team_ranking.sort_by_key(|standing| Reverse(standing.score));
+
+    for i in 0..team_ranking.len() {
+        team_ranking[i].rank = if i > 0 && team_ranking[i].score == team_ranking[i - 1].score {
+            team_ranking[i - 1].rank
+        } else {
+            i + 1
+        };
+    }
+
+    team_ranking
+}
+
+/// Finds a player's standing in a ranking, along with how many points behind
+/// the next best player they are.
+pub fn find_standing<'a>(ranking: &'a [Standing], username: &str) -> Option<(&'a Standing, Option<u32>)> {
+    let position = ranking.iter().position(|standing| standing.username == username)?;
+    let standing = &ranking[position];
+
+    let gap = ranking[..position]
+        .iter()
+        .rev()
+        .find(|above| above.score > standing.score)
+        .map(|above| above.score - standing.score);
+
+    Some((standing, gap))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use crate::ws::api::{RoomSettings, ScoringSettings, TeamAssignment, TeamScoring, TeamSettings};
+
+    use super::{find_standing, rank_teams, Scoreboard};
+
+    #[test]
+    fn ranking_with_ties() {
+        let settings = RoomSettings {
+            streak_bonus: 0,
+            ..RoomSettings::default()
+        };
+        let mut scoreboard = Scoreboard::new(&settings);
+        let players = HashSet::from(["Alice", "Bob", "Chris", "Dave", "Eve"].map(String::from));
+
+        scoreboard.add_round(0, &players, &mut HashMap::from([
+            (String::from("Alice"), 1000),
+            (String::from("Bob"), 500),
+            (String::from("Chris"), 500),
+        ]), &HashSet::from([String::from("Alice")]), true);
+        scoreboard.add_round(1, &players, &mut HashMap::from([
+            (String::from("Bob"), 100),
+            (String::from("Dave"), 500),
+        ]), &HashSet::from([String::from("Alice"), String::from("Dave")]), true);
+
+        let ranking = scoreboard.rank(&players);
+
+        let ranks: Vec<_> = ranking
+            .iter()
+            .map(|s| (s.username.as_str(), s.score, s.correct, s.rank))
+            .collect();
+        assert_eq!(
+            ranks,
+            vec![
+                ("Alice", 1000, 2, 1),
+                ("Bob", 600, 0, 2),
+                ("Chris", 500, 0, 3),
+                ("Dave", 500, 1, 3),
+                ("Eve", 0, 0, 5),
+            ]
+        );
+        assert_eq!(scoreboard.graded_rounds(), 2);
+
+        let (_, gap) = find_standing(&ranking, "Alice").unwrap();
+        assert_eq!(gap, None);
+        let (_, gap) = find_standing(&ranking, "Dave").unwrap();
+        assert_eq!(gap, Some(100));
+        assert!(find_standing(&ranking, "Frank").is_none());
+    }
+
+    #[test]
+    fn streak_bonuses() {
+        let settings = RoomSettings {
+            streak_bonus: 100,
+            max_streak_bonus: 250,
+            ..RoomSettings::default()
+        };
+        let mut scoreboard = Scoreboard::new(&settings);
+        let players = HashSet::from([String::from("Alice"), String::from("Bob")]);
+        let alice = HashSet::from([String::from("Alice")]);
+
+        let mut question = 0;
+        let mut round = |correct: &HashSet<String>, graded| {
+            let mut point_gains: HashMap<_, _> = correct.iter().map(|name| (name.clone(), 500)).collect();
+            scoreboard.add_round(question, &players, &mut point_gains, correct, graded);
+            question += 1;
+            point_gains.get("Alice").copied()
+        };
+
+        assert_eq!(round(&alice, true), Some(500));
+        assert_eq!(round(&alice, true), Some(600));
+        // Polls don't break streaks
+        assert_eq!(round(&HashSet::new(), false), None);
+        assert_eq!(round(&alice, true), Some(700));
+        assert_eq!(round(&alice, true), Some(750));
+        // Wrong answers do
+        assert_eq!(round(&HashSet::new(), true), None);
+        assert_eq!(round(&alice, true), Some(500));
+
+        let ranking = scoreboard.rank(&players);
+        assert_eq!((ranking[0].streak, ranking[1].streak), (1, 0));
+    }
+
+    #[test]
+    fn huge_bonuses_saturate() {
+        let settings = RoomSettings {
+            streak_bonus: 3_000_000_000,
+            max_streak_bonus: u32::MAX,
+            ..RoomSettings::default()
+        };
+        let mut scoreboard = Scoreboard::new(&settings);
+        let players = HashSet::from([String::from("Alice")]);
+
+        for question in 0..4 {
+            let mut point_gains = HashMap::from([(String::from("Alice"), u32::MAX)]);
+            scoreboard.add_round(question, &players, &mut point_gains, &players, true);
+            assert_eq!(point_gains["Alice"], u32::MAX);
+        }
+
+        assert_eq!(scoreboard.record("Alice").score, u32::MAX);
+    }
+
+    #[test]
+    fn accuracy_only_has_no_bonus() {
+        let settings = RoomSettings {
+            scoring: ScoringSettings::AccuracyOnly,
+            ..RoomSettings::default()
+        };
+        let mut scoreboard = Scoreboard::new(&settings);
+        let players = HashSet::from([String::from("Alice")]);
+
+        for question in 0..3 {
+            let mut point_gains = HashMap::from([(String::from("Alice"), 1)]);
+            scoreboard.add_round(question, &players, &mut point_gains, &players, true);
+            assert_eq!(point_gains["Alice"], 1);
+        }
+
+        // The score is just the number of correct answers
+        assert_eq!(scoreboard.record("Alice").score, 3);
+    }
+
+    #[test]
+    fn team_scores() {
+        let mut scoreboard = Scoreboard::new(&RoomSettings::default());
+        let players = HashSet::from(["Alice", "Bob", "Chris"].map(String::from));
+        scoreboard.add_round(0, &players, &mut HashMap::from([
+            (String::from("Alice"), 900),
+            (String::from("Bob"), 300),
+            (String::from("Chris"), 1000),
+        ]), &HashSet::new(), false);
+        let ranking = scoreboard.rank(&players);
+
+        let members = HashMap::from([
+            (String::from("Alice"), String::from("Red")),
+            (String::from("Bob"), String::from("Red")),
+            (String::from("Chris"), String::from("Blue")),
+        ]);
+        let scores = |scoring| {
+            let teams = TeamSettings {
+                names: ["Red", "Blue", "Green"].map(String::from).to_vec(),
+                assignment: TeamAssignment::Balanced,
+                scoring,
+            };
+            rank_teams(&ranking, &teams, &members)
+                .into_iter()
+                .map(|standing| (standing.team, standing.score, standing.members, standing.rank))
+                .collect::<Vec<_>>()
+        };
+        let team = |name: &str, score, members, rank| (String::from(name), score, members, rank);
+
+        assert_eq!(
+            scores(TeamScoring::Sum),
+            vec![team("Red", 1200, 2, 1), team("Blue", 1000, 1, 2), team("Green", 0, 0, 3)]
+        );
+        assert_eq!(
+            scores(TeamScoring::Average),
+            vec![team("Blue", 1000, 1, 1), team("Red", 600, 2, 2), team("Green", 0, 0, 3)]
+        );
+        assert_eq!(
+            scores(TeamScoring::Best),
+            vec![team("Blue", 1000, 1, 1), team("Red", 900, 2, 2), team("Green", 0, 0, 3)]
+        );
+    }
+
+    #[test]
+    fn replays_overwrite() {
+        let settings = RoomSettings {
+            streak_bonus: 0,
+            ..RoomSettings::default()
+        };
+        let mut scoreboard = Scoreboard::new(&settings);
+        let players = HashSet::from([String::from("Alice"), String::from("Bob")]);
+
+        scoreboard.add_round(0, &players, &mut HashMap::from([
+            (String::from("Alice"), 800),
+        ]), &HashSet::from([String::from("Alice")]), true);
+        scoreboard.add_round(1, &players, &mut HashMap::from([
+            (String::from("Alice"), 300),
+        ]), &HashSet::from([String::from("Alice")]), true);
+
+        // The first question is replayed, and only Bob gets it right this time
+        scoreboard.add_round(0, &players, &mut HashMap::from([
+            (String::from("Bob"), 600),
+        ]), &HashSet::from([String::from("Bob")]), true);
+
+        let alice = scoreboard.record("Alice");
+        let bob = scoreboard.record("Bob");
+        assert_eq!((alice.score, alice.correct), (300, 1));
+        assert_eq!((bob.score, bob.correct), (600, 1));
+        assert_eq!(scoreboard.graded_rounds(), 2);
+    }
+}
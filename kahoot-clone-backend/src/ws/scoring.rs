@@ -0,0 +1,213 @@
+use super::api::{PointMultiplier, Question, ScoringSettings, Submission};
+use super::scores::PlayerRecord;
+
+use std::time::Duration;
+
+/// Everything known about an answer when it is scored.
+// None of the built-in strategies look at the submission or history, but
+// they're here for strategies that want them.
+#[allow(dead_code)]
+pub struct ScoredAnswer<'a> {
+    pub question: &'a Question,
+    pub submission: &'a Submission,
+    /// How correct the answer was, from `0.0` to `1.0`.
+    pub credit: f64,
+    /// How long after the round began the answer came in.
+    pub latency: Duration,
+    /// The number of answers which earned credit this round before this one.
+    pub order: usize,
+    /// The player's results from earlier rounds.
+    pub history: PlayerRecord,
+}
+
+/// A formula for turning answers into points.
+///
+/// Strategies don't need to deal with wrong answers or point multipliers,
+/// the game does that for them.
+//
+// `Send` and `Sync` are needed so the strategy can be used inside a spawned
+// task.
+//
+// Relevant: https://doc.rust-lang.org/book/ch17-02-trait-objects.html
+pub trait ScoringStrategy: Send + Sync {
+    /// The points an answer which earned some credit is worth.
+    fn points(&self, answer: &ScoredAnswer) -> u32;
+
+    /// Whether correct answers in a row earn a streak bonus on top of these
+    /// points.
+    fn streak_bonuses(&self) -> bool {
+        true
+    }
+}
+
+/// Faster answers earn more points.
+///
+/// An instant answer earns `max_points`, and an answer right at the end of the
+/// round earns `min_points`.
+pub struct TimeBased {
+    pub max_points: u32,
+    pub min_points: u32,
+}
+
+/// Earlier answers earn more points.
+///
+/// The first answer earns `first_points`, and each answer after it earns 10/11
+/// of the one before.
+pub struct OrderBased {
+    pub first_points: u32,
+}
+
+/// Every answer earns the same points.
+pub struct Flat {
+    pub points: u32,
+}
+
+/// Every completely correct answer earns a single point, so scores count
+/// correct answers.
+pub struct AccuracyOnly;
+
+impl ScoringStrategy for TimeBased {
+    fn points(&self, answer: &ScoredAnswer) -> u32 {
+        let time = Duration::from_secs(answer.question.time as u64);
+
+        // How much of the round was left, from `0.0` to `1.0`
+        let remaining = if time.is_zero() {
+            0.0
+        } else {
+            1.0 - (answer.latency.as_secs_f64() / time.as_secs_f64()).min(1.0)
+        };
+
+        let (max, min) = (self.max_points as f64, self.min_points as f64);
+        let points = min + (max - min) * remaining;
+
+        (points * answer.credit).round() as u32
+    }
+}
+
+impl ScoringStrategy for OrderBased {
+    fn points(&self, answer: &ScoredAnswer) -> u32 {
+        // Worked out in `u64` so big point values can't overflow
+        let mut points = self.first_points as u64;
+        for _ in 0..answer.order {
+            points = (points * 10 / 11).max(1);
+        }
+
+        (points as f64 * answer.credit).round() as u32
+    }
+}
+
+impl ScoringStrategy for Flat {
+    fn points(&self, answer: &ScoredAnswer) -> u32 {
+        (self.points as f64 * answer.credit).round() as u32
+    }
+}
+
+impl ScoringStrategy for AccuracyOnly {
+    fn points(&self, answer: &ScoredAnswer) -> u32 {
+        u32::from(answer.credit >= 1.0)
+    }
+
+    // Bonus points would stop scores counting correct answers
+    fn streak_bonuses(&self) -> bool {
+        false
+    }
+}
+
+impl ScoringSettings {
+    /// Creates the strategy these settings describe.
+    pub fn strategy(&self) -> Box<dyn ScoringStrategy> {
+        match *self {
+            ScoringSettings::TimeBased { max_points, min_points } => {
+                Box::new(TimeBased { max_points, min_points })
+            }
+            ScoringSettings::OrderBased { first_points } => Box::new(OrderBased { first_points }),
+            ScoringSettings::Flat { points } => Box::new(Flat { points }),
+            ScoringSettings::AccuracyOnly => Box::new(AccuracyOnly),
+        }
+    }
+}
+
+impl PointMultiplier {
+    /// Multiplies points by how much a question is worth.
+    pub fn apply(self, points: u32) -> u32 {
+        match self {
+            PointMultiplier::Standard => points,
+            PointMultiplier::Double => points.saturating_mul(2),
+            PointMultiplier::NoPoints => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::ws::api::{PointMultiplier, Question, QuestionKind, ScoringSettings, Submission};
+    use crate::ws::scores::PlayerRecord;
+
+    use super::ScoredAnswer;
+
+    fn score(settings: ScoringSettings, secs: u64, credit: f64, order: usize) -> u32 {
+        let question = Question {
+            question: String::from("Fish?"),
+            media: None,
+            kind: QuestionKind::MultipleChoice {
+                choices: vec!["foo".into(), "bar".into()],
+                answer: 0,
+            },
+            time: 20,
+            points: PointMultiplier::Standard,
+        };
+
+        settings.strategy().points(&ScoredAnswer {
+            question: &question,
+            submission: &Submission::Choice { choice: 0 },
+            credit,
+            latency: Duration::from_secs(secs),
+            order,
+            history: PlayerRecord::default(),
+        })
+    }
+
+    #[test]
+    fn time_based() {
+        let settings = ScoringSettings::default();
+
+        assert_eq!(score(settings, 0, 1.0, 3), 1000);
+        assert_eq!(score(settings, 10, 1.0, 0), 750);
+        assert_eq!(score(settings, 20, 1.0, 0), 500);
+        assert_eq!(score(settings, 30, 1.0, 0), 500);
+        assert_eq!(score(settings, 10, 0.5, 0), 375);
+    }
+
+    #[test]
+    fn order_based() {
+        let settings = ScoringSettings::OrderBased { first_points: 1000 };
+
+        assert_eq!(score(settings, 10, 1.0, 0), 1000);
+        assert_eq!(score(settings, 0, 1.0, 1), 909);
+        assert_eq!(score(settings, 0, 1.0, 2), 826);
+        assert_eq!(score(settings, 0, 0.5, 0), 500);
+
+        let huge = ScoringSettings::OrderBased { first_points: u32::MAX };
+        assert_eq!(score(huge, 0, 1.0, 1), 3_904_515_722);
+    }
+
+    #[test]
+    fn flat_and_accuracy_only() {
+        let flat = ScoringSettings::Flat { points: 100 };
+        assert_eq!(score(flat, 15, 1.0, 5), 100);
+        assert_eq!(score(flat, 15, 0.25, 5), 25);
+
+        let accuracy = ScoringSettings::AccuracyOnly;
+        assert_eq!(score(accuracy, 15, 1.0, 5), 1);
+        assert_eq!(score(accuracy, 0, 0.5, 0), 0);
+    }
+
+    #[test]
+    fn multipliers() {
+        assert_eq!(PointMultiplier::Standard.apply(800), 800);
+        assert_eq!(PointMultiplier::Double.apply(800), 1600);
+        assert_eq!(PointMultiplier::NoPoints.apply(800), 0);
+    }
+}
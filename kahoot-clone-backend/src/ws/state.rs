@@ -1,7 +1,12 @@
-use super::api::RoomId;
+use super::api::{
+    AnswerOutcome, LateJoin, Media, Prompt, ReplayPoints, RoomId, RoundTiming, Solution, Standing, Submission, TeamAssignment,
+    TeamSettings, TeamStanding, UserEvent,
+};
 
 use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use tokio::sync::{mpsc, watch, oneshot};
 
@@ -24,37 +29,178 @@ pub struct Room {
     pub users: Users,
     pub action_stream: mpsc::Sender<PlayerAnswer>,
     pub result_stream: watch::Receiver<GameEvent>,
+    /// When players can join once the game has started.
+    pub late_join: LateJoin,
+    /// What the game is doing, for players who join late.
+    pub phase: Mutex<Phase>,
+    /// Everyone's standing as of the last round, for players who rejoin.
+    pub standings: Mutex<Arc<Vec<Standing>>>,
+}
+
+/// What a room's game is doing right now.
+///
+/// Unlike the latest `GameEvent`, this always says which question is being
+/// asked, so players joining late can be caught up.
+#[derive(Clone)]
+pub enum Phase {
+    Lobby,
+    GetReady {
+        question: String,
+        round_id: u32,
+        opens_at: u64,
+    },
+    Round {
+        question: String,
+        prompt: Prompt,
+        media: Option<Media>,
+        round_id: u32,
+        /// When the round ends, unless it is paused.
+        ends: Instant,
+        /// The time left while the round is paused.
+        paused: Option<Duration>,
+        /// The players who already answered.
+        answered: HashSet<String>,
+    },
+    BetweenRounds,
 }
 
 pub struct Users {
     pub users: Arc<Mutex<UserMap>>,
     event_stream: mpsc::Sender<PlayerEvent>,
+    /// How players are split into teams, if this is a team game.
+    teams: Option<TeamSettings>,
+    /// How long players who lose their connection keep their place.
+    grace: Duration,
+    /// Whether new players are kept out of the room.
+    locked: AtomicBool,
+    /// Names the host banned from the room, normalized by `name_key`.
+    banned: Mutex<HashSet<String>>,
+    /// The session given to the next connection, so no two connections in
+    /// the room ever share one.
+    next_session: AtomicU32,
 }
 
-type UserMap = HashSet<String>;
+type UserMap = HashMap<String, Member>;
+
+/// A player in a room.
+pub struct Member {
+    /// The player's team, in team games.
+    pub team: Option<String>,
+    /// Sends events straight to this player.
+    pub events: mpsc::Sender<UserEvent>,
+    /// Closes the player's connection without going through `events`.
+    close: oneshot::Sender<()>,
+    /// Lets the player rejoin after losing their connection.
+    resume_token: String,
+    /// Identifies the player's current connection, so leaving from an old
+    /// connection, or one of a kicked player with the same name, can't
+    /// remove them.
+    session: u32,
+    /// Whether the player's connection is open. Players who lost theirs
+    /// keep their place for the grace period, but aren't waited for.
+    pub connected: bool,
+}
+
+/// A user who joined or rejoined a room.
+pub struct JoinedUser {
+    /// Keeps the user in the room until it is dropped.
+    pub presence: UserPresence,
+    pub username: String,
+    /// The team the user is on, in team games.
+    pub team: Option<String>,
+    pub resume_token: String,
+    /// Fires if the user's connection has to be closed without telling
+    /// them why first, eg. because they stopped keeping up.
+    pub closed: oneshot::Receiver<()>,
+}
 
-pub struct UserPresence(String, Arc<Mutex<UserMap>>, Option<oneshot::Sender<()>>);
+pub struct UserPresence(Option<oneshot::Sender<()>>);
 
 pub struct PlayerAnswer {
     pub username: String,
-    pub choice: usize,
+    /// The round the player was answering.
+    pub round_id: u32,
+    pub answer: Submission,
+    /// When the server received the answer.
+    pub received: Instant,
+}
+
+/// Keeps track of which question is asked next.
+pub struct Progress {
+    /// Where the game carries on from, ignoring replays.
+    next: usize,
+    total: usize,
+    /// The questions which were already asked.
+    played: HashSet<usize>,
+    /// The question the host asked to replay next, and how it is scored.
+    replay: Option<(usize, ReplayPoints)>,
+}
+
+/// Why a user couldn't join a room.
+#[derive(Debug)]
+pub enum JoinError {
+    Duplicate,
+    Locked,
+    Banned,
+    /// The room only lets players join in the lobby.
+    GameStarted,
+    /// The room only lets players join between rounds.
+    RoundInProgress,
+    /// No player in the room has the resume token.
+    UnknownToken,
+}
+
+/// Why a host couldn't move a player to another team.
+#[derive(Debug)]
+pub enum AssignError {
+    NoTeams,
+    UnknownTeam,
+    UnknownUser,
 }
 
 #[derive(Clone)]
 pub enum GameEvent {
     InLobby,
+    GetReady {
+        question: String,
+        round_id: u32,
+        opens_at: u64,
+    },
     RoundBegin {
-        choices: Vec<String>,
+        question: String,
+        prompt: Prompt,
+        media: Option<Media>,
+        timing: RoundTiming,
     },
+    /// The round timer was frozen with this many seconds left.
+    Paused(f64),
+    /// The round timer started again with this many seconds left, and will
+    /// now end at the deadline.
+    Resumed { remaining: f64, deadline: u64 },
+    Tick { round_id: u32, remaining: f64 },
     RoundEnd {
         point_gains: Arc<HashMap<String, u32>>,
+        /// What each player answered, and how it went.
+        answers: Arc<HashMap<String, (Submission, AnswerOutcome)>>,
+        solution: Option<Solution>,
+        ranking: Arc<Vec<Standing>>,
+        teams: Option<Arc<Vec<TeamStanding>>>,
+    },
+    GameEnd {
+        ranking: Arc<Vec<Standing>>,
+        graded_rounds: u32,
+        teams: Option<Arc<Vec<TeamStanding>>>,
     },
-    GameEnd,
 }
 
 pub enum PlayerEvent {
-    Joined(String),
+    Joined { username: String, team: Option<String> },
+    /// The player lost their connection, but keeps their place for now.
+    Disconnected(String),
+    Rejoined(String),
     Left(String),
+    /// The host kicked the player, who has left the room.
+    Kicked { username: String, banned: bool },
 }
 
 impl State {
@@ -69,9 +215,8 @@ impl State {
 
     pub async fn remove_room(&self, room_id: &RoomId) {
         let mut rooms = self.rooms.lock().unwrap();
-        if rooms.remove(&room_id).is_none() {
+        if rooms.remove(room_id).is_none() {
             tracing::debug!("Room `{room_id}` doesn't exist");
-            return;
         }
     }
 
@@ -80,79 +225,508 @@ impl State {
     }
 }
 
+impl Room {
+    pub fn phase(&self) -> Phase {
+        self.phase.lock().unwrap().clone()
+    }
+
+    pub fn set_phase(&self, phase: Phase) {
+        *self.phase.lock().unwrap() = phase;
+    }
+
+    /// Freezes the clock of the round in progress with `remaining` left, or
+    /// starts it again if it isn't `paused`.
+    pub fn set_round_clock(&self, remaining: Duration, is_paused: bool) {
+        if let Phase::Round { ends, paused, .. } = &mut *self.phase.lock().unwrap() {
+            *ends = Instant::now() + remaining;
+            *paused = is_paused.then_some(remaining);
+        }
+    }
+
+    /// Notes that a player answered the round in progress.
+    pub fn mark_answered(&self, username: &str) {
+        if let Phase::Round { answered, .. } = &mut *self.phase.lock().unwrap() {
+            answered.insert(username.to_string());
+        }
+    }
+
+    pub fn standings(&self) -> Arc<Vec<Standing>> {
+        Arc::clone(&self.standings.lock().unwrap())
+    }
+
+    pub fn set_standings(&self, standings: Arc<Vec<Standing>>) {
+        *self.standings.lock().unwrap() = standings;
+    }
+
+    /// Checks whether the room's late join policy lets players join now.
+    pub fn check_late_join(&self) -> Result<(), JoinError> {
+        match (self.late_join, self.phase()) {
+            (_, Phase::Lobby) | (LateJoin::Allowed, _) | (LateJoin::BetweenRounds, Phase::BetweenRounds) => Ok(()),
+            (LateJoin::LobbyOnly, _) => Err(JoinError::GameStarted),
+            (LateJoin::BetweenRounds, _) => Err(JoinError::RoundInProgress),
+        }
+    }
+}
+
+impl Progress {
+    pub fn new(total: usize) -> Self {
+        Self {
+            next: 0,
+            total,
+            played: HashSet::new(),
+            replay: None,
+        }
+    }
+
+    /// The index of the next question, or `None` if there are none left.
+    pub fn next(&self) -> Option<usize> {
+        if let Some((index, _)) = self.replay {
+            return Some(index);
+        }
+
+        // Questions which were already played aren't asked again
+        (self.next..self.total).find(|index| !self.played.contains(index))
+    }
+
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Moves on to the next question, returning its index.
+    ///
+    /// Also returns how to score it if it is a replay, and `None` otherwise.
+    /// The game carries on from where it was after a replay.
+    pub fn start_round(&mut self) -> Option<(usize, Option<ReplayPoints>)> {
+        if let Some((index, points)) = self.replay.take() {
+            return Some((index, Some(points)));
+        }
+
+        let index = self.next()?;
+        self.next = index + 1;
+        self.played.insert(index);
+
+        Some((index, None))
+    }
+
+    pub fn skip(&mut self) -> Result<(), &'static str> {
+        // Skipping a replay goes back to where the game was
+        if self.replay.take().is_some() {
+            return Ok(());
+        }
+
+        let index = self.next().ok_or("There is no question to skip")?;
+        self.next = index + 1;
+        Ok(())
+    }
+
+    pub fn jump_to(&mut self, index: usize) -> Result<(), &'static str> {
+        if index >= self.total {
+            return Err("There is no question with that index");
+        }
+        if self.played.contains(&index) {
+            return self.replay(index, ReplayPoints::Overwrite);
+        }
+        self.next = index;
+        self.replay = None;
+        Ok(())
+    }
+
+    pub fn replay(&mut self, index: usize, points: ReplayPoints) -> Result<(), &'static str> {
+        if !self.played.contains(&index) {
+            return Err("That question hasn't been played yet");
+        }
+        self.replay = Some((index, points));
+        Ok(())
+    }
+}
+
 impl Users {
-    pub fn new() -> (Self, mpsc::Receiver<PlayerEvent>) {
+    /// `grace` is how long players who lose their connection keep their
+    /// place in the room.
+    pub fn new(teams: Option<TeamSettings>, grace: Duration) -> (Self, mpsc::Receiver<PlayerEvent>) {
         let (tx, rx) = mpsc::channel(30);
 
-        let users = Arc::new(Mutex::new(HashSet::new()));
+        let users = Arc::new(Mutex::new(HashMap::new()));
 
         let users = Self {
             users,
             event_stream: tx,
+            teams,
+            grace,
+            locked: AtomicBool::new(false),
+            banned: Mutex::new(HashSet::new()),
+            next_session: AtomicU32::new(0),
         };
 
         (users, rx)
     }
 
+    /// The number of players whose connection is open.
     pub fn player_count(&self) -> usize {
-        self.users.lock().unwrap().len()
+        self.users.lock().unwrap().values().filter(|member| member.connected).count()
+    }
+
+    /// The usernames of everyone in the room.
+    pub fn usernames(&self) -> HashSet<String> {
+        self.users.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Every player's team, in team games.
+    pub fn team_members(&self) -> Option<(&TeamSettings, HashMap<String, String>)> {
+        let teams = self.teams.as_ref()?;
+        let members = self
+            .users
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(username, member)| Some((username.clone(), member.team.clone()?)))
+            .collect();
+
+        Some((teams, members))
     }
 
     /// Tries to add a user to the user map.
-    /// Returns a `Some(UserPresence)` on success and `None` on failure.
-    pub async fn join_user(&self, name: String) -> Option<UserPresence> {
-        {
+    ///
+    /// `team` is the team the user asked for, and `events` sends events
+    /// straight to them. Returns why the user couldn't join on failure.
+    pub async fn join_user(
+        &self,
+        name: String,
+        team: Option<String>,
+        events: mpsc::Sender<UserEvent>,
+    ) -> Result<JoinedUser, JoinError> {
+        if self.banned.lock().unwrap().contains(&name_key(&name)) {
+            return Err(JoinError::Banned);
+        }
+        if self.locked.load(Ordering::SeqCst) {
+            return Err(JoinError::Locked);
+        }
+
+        let resume_token = format!("{:032x}", rand::random::<u128>());
+        let session = self.next_session.fetch_add(1, Ordering::SeqCst);
+        let (close, closed) = oneshot::channel();
+
+        let team = {
             tracing::debug!("Accquiring users lock to add new user...");
             let mut users = self.users.lock().unwrap();
             tracing::debug!("Lock accquired.");
 
-            if users.contains(&name) {
-                return None;
+            // Names which only differ in case or spaces count as the same
+            let key = name_key(&name);
+            if users.keys().any(|existing| name_key(existing) == key) {
+                return Err(JoinError::Duplicate);
             }
 
+            let team = self.teams.as_ref().map(|teams| pick_team(teams, &users, team));
+
             tracing::debug!("Adding `{name}`...");
-            let name = name.clone();
-            users.insert(name);
+            let member = Member {
+                team: team.clone(),
+                events,
+                close,
+                resume_token: resume_token.clone(),
+                session,
+                connected: true,
+            };
+            users.insert(name.clone(), member);
 
             tracing::debug!("User added.");
-        }
+            team
+        };
 
         // Emitting join event
         let _ = self
             .event_stream
-            .send(PlayerEvent::Joined(name.clone()))
+            .send(PlayerEvent::Joined { username: name.clone(), team: team.clone() })
             .await;
 
+        Ok(JoinedUser {
+            presence: self.presence(name.clone(), session),
+            username: name,
+            team,
+            resume_token,
+            closed,
+        })
+    }
+
+    /// Puts a player who lost their connection back in the room, with the
+    /// same name, team and score.
+    ///
+    /// The player's old connection is told it was replaced and closed, if
+    /// it is somehow still open.
+    pub async fn rejoin_user(&self, resume_token: &str, events: mpsc::Sender<UserEvent>) -> Result<JoinedUser, JoinError> {
+        let (close, closed) = oneshot::channel();
+        let (name, team, session) = {
+            let mut users = self.users.lock().unwrap();
+            let (name, member) = users
+                .iter_mut()
+                .find(|(_, member)| member.resume_token == resume_token)
+                .ok_or(JoinError::UnknownToken)?;
+
+            // The old connection closes once it gets this, or straight away
+            // if it can't be told
+            let old_events = std::mem::replace(&mut member.events, events);
+            let old_close = std::mem::replace(&mut member.close, close);
+            end_connection(name, &old_events, old_close, UserEvent::Replaced);
+
+            member.session = self.next_session.fetch_add(1, Ordering::SeqCst);
+            member.connected = true;
+            (name.clone(), member.team.clone(), member.session)
+        };
+
+        let _ = self.event_stream.send(PlayerEvent::Rejoined(name.clone())).await;
+
+        Ok(JoinedUser {
+            presence: self.presence(name.clone(), session),
+            username: name,
+            team,
+            resume_token: resume_token.to_string(),
+            closed,
+        })
+    }
+
+    /// Creates the presence for one connection of a player.
+    ///
+    /// Once it's dropped the player keeps their place for the grace period,
+    /// then they are removed and the host is told they left. Rejoining in
+    /// the meantime starts a new session, which this presence leaves alone.
+    fn presence(&self, name: String, session: u32) -> UserPresence {
         // Copy the necessary values
         let user_map = Arc::clone(&self.users);
         let event_stream = self.event_stream.clone();
-        let username = name.clone();
-        
+        let grace = self.grace;
+
         // Set up oneshot channel for leave message
         let (leave_tx, leave_rx) = oneshot::channel();
         tokio::spawn(async move {
             // Wait for oneshot leave message
             let _ = leave_rx.await;
+
+            // Kicked players are already gone, so they don't keep a place
+            let keep = !grace.is_zero() && {
+                let mut users = user_map.lock().unwrap();
+                match users.get_mut(&name).filter(|member| member.session == session) {
+                    Some(member) => {
+                        member.connected = false;
+                        true
+                    }
+                    None => false,
+                }
+            };
+            if keep {
+                let _ = event_stream.send(PlayerEvent::Disconnected(name.clone())).await;
+                tokio::time::sleep(grace).await;
+            }
+
+            // Remove the player, unless they came back
+            let left = {
+                let mut users = user_map.lock().unwrap();
+                let left = session_member(&users, &name, session).is_some();
+                if left {
+                    users.remove(&name);
+                }
+                left
+            };
+
             // Emit player event to host
-            let _ = event_stream.send(PlayerEvent::Left(username)).await;
+            if left {
+                let _ = event_stream.send(PlayerEvent::Left(name)).await;
+            }
         });
 
-        Some(UserPresence(name, user_map, Some(leave_tx)))
+        UserPresence(Some(leave_tx))
     }
+
+    /// Sends an event straight to a player, if they're in the room.
+    ///
+    /// This never waits, so a player who stops reading their socket can't
+    /// hold up the game. The event is dropped if their channel is full.
+    pub fn send_to(&self, username: &str, event: UserEvent) {
+        let users = self.users.lock().unwrap();
+        let member = if let Some(member) = users.get(username) {
+            member
+        } else {
+            return;
+        };
+
+        if let Err(mpsc::error::TrySendError::Full(event)) = member.events.try_send(event) {
+            tracing::warn!("Dropped {event:?} for `{username}`, who isn't keeping up");
+        }
+    }
+
+    /// Moves a player to another team and tells them about it.
+    ///
+    /// Like `send_to`, this never waits on the player.
+    pub fn assign_team(&self, username: &str, team: &str) -> Result<(), AssignError> {
+        let teams = self.teams.as_ref().ok_or(AssignError::NoTeams)?;
+        if !teams.names.iter().any(|name| name == team) {
+            return Err(AssignError::UnknownTeam);
+        }
+
+        {
+            let mut users = self.users.lock().unwrap();
+            let member = users.get_mut(username).ok_or(AssignError::UnknownUser)?;
+            member.team = Some(team.to_string());
+        }
+
+        self.send_to(username, UserEvent::TeamAssigned { team: team.to_string() });
+        Ok(())
+    }
+
+    /// Keeps new players out of the room, or lets them in again.
+    pub fn set_locked(&self, locked: bool) {
+        self.locked.store(locked, Ordering::SeqCst);
+    }
+
+    /// Removes a player from the room, and stops the name joining again if
+    /// `ban` is set.
+    ///
+    /// The player is removed straight away, even if they're disconnected,
+    /// then sent `Kicked` so their connection closes. This never waits on
+    /// the player, so one who stops reading their socket can't freeze the
+    /// room. Names are compared
+    /// like bans, and can be banned even if nobody is using them. Returns
+    /// whether a player was kicked.
+    pub async fn kick(&self, username: &str, ban: bool) -> bool {
+        if ban {
+            self.banned.lock().unwrap().insert(name_key(username));
+        }
+
+        // Names are matched like bans, so banning "troll" kicks "Troll" too
+        let kicked = {
+            let mut users = self.users.lock().unwrap();
+            let key = name_key(username);
+            let name = users.keys().find(|name| name_key(name) == key).cloned();
+            name.and_then(|name| Some((users.remove(&name)?, name)))
+        };
+        let (member, username) = if let Some(kicked) = kicked {
+            kicked
+        } else {
+            return false;
+        };
+
+        let _ = self.event_stream.send(PlayerEvent::Kicked { username: username.clone(), banned: ban }).await;
+        end_connection(&username, &member.events, member.close, UserEvent::Kicked { banned: ban });
+        true
+    }
+
+    /// Lets a banned name join again. Returns whether the name was banned.
+    pub fn unban(&self, username: &str) -> bool {
+        self.banned.lock().unwrap().remove(&name_key(username))
+    }
+}
+
+/// Sends a player an event their connection closes after, like `Kicked`.
+///
+/// If their channel is full the event is dropped, and the connection is
+/// closed straight away instead.
+fn end_connection(username: &str, events: &mpsc::Sender<UserEvent>, close: oneshot::Sender<()>, event: UserEvent) {
+    if let Err(mpsc::error::TrySendError::Full(event)) = events.try_send(event) {
+        tracing::warn!("Couldn't send {event:?} to `{username}`, who isn't keeping up, closing their connection...");
+        let _ = close.send(());
+    }
+}
+
+/// A player's details, if they're still on the connection with `session`.
+fn session_member<'a>(users: &'a UserMap, name: &str, session: u32) -> Option<&'a Member> {
+    users.get(name).filter(|member| member.session == session)
+}
+
+/// The form of a name used to compare names, so a banned player can't get
+/// back in, or pose as someone else, by changing case or adding spaces.
+fn name_key(username: &str) -> String {
+    username.trim().to_lowercase()
+}
+
+/// Picks the team a joining player is put on.
+///
+/// Players get the team they asked for if they're allowed to choose,
+/// otherwise they're put on the team with the fewest players.
+fn pick_team(teams: &TeamSettings, users: &UserMap, wanted: Option<String>) -> String {
+    if teams.assignment == TeamAssignment::Balanced {
+        if let Some(wanted) = wanted.filter(|wanted| teams.names.contains(wanted)) {
+            return wanted;
+        }
+    }
+
+    // `min_by_key` picks the first of equally small teams
+    teams
+        .names
+        .iter()
+        .min_by_key(|name| {
+            users
+                .values()
+                .filter(|member| member.team.as_ref() == Some(*name))
+                .count()
+        })
+        .cloned()
+        .unwrap_or_default()
 }
 
 impl Drop for UserPresence {
-    /// Removes user from user map and emits a signal.
+    /// Emits a signal, so the user is removed from the user map once their
+    /// grace period is over.
     fn drop(&mut self) {
-        let UserPresence(name, user_map, leave_tx) = self;
-
         // Emit event and ignore any errors
-        if let Some(tx) = leave_tx.take() {
+        if let Some(tx) = self.0.take() {
             let _ = tx.send(());
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::sync::mpsc;
+
+    use crate::ws::api::{TeamAssignment, TeamSettings, UserEvent};
+
+    use super::Users;
+
+    #[tokio::test]
+    async fn kick_stalled_player() {
+        let (users, _player_events) = Users::new(None, Duration::ZERO);
+        let (events, _user_events) = mpsc::channel(1);
+        let joined = users.join_user(String::from("Troll"), None, events.clone()).await.unwrap();
+
+        // The player stopped reading their socket, so their channel is full
+        events.try_send(UserEvent::AnswerAccepted).unwrap();
+
+        // Kicking doesn't wait for room in the channel, and closes the
+        // connection instead of telling them
+        assert!(users.kick("Troll", false).await);
+        assert!(joined.closed.await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn assign_stalled_player() {
+        let teams = TeamSettings {
+            names: ["Red", "Blue"].map(String::from).to_vec(),
+            assignment: TeamAssignment::Balanced,
+            scoring: Default::default(),
+        };
+        let (users, _player_events) = Users::new(Some(teams), Duration::ZERO);
+        let (events, _user_events) = mpsc::channel(1);
+        let _joined = users.join_user(String::from("Alice"), None, events.clone()).await.unwrap();
+        events.try_send(UserEvent::AnswerAccepted).unwrap();
+
+        // The move happens even though the player can't be told about it
+        assert!(users.assign_team("Alice", "Blue").is_ok());
+        let (_, members) = users.team_members().unwrap();
+        assert_eq!(members["Alice"], "Blue");
+    }
+
+    #[tokio::test]
+    async fn replace_stalled_connection() {
+        let (users, _player_events) = Users::new(None, Duration::from_secs(30));
+        let (events, _user_events) = mpsc::channel(1);
+        let joined = users.join_user(String::from("Alice"), None, events.clone()).await.unwrap();
+        events.try_send(UserEvent::AnswerAccepted).unwrap();
 
-        // Remove from user map
-        let mut user_map = user_map.lock().unwrap();
-        user_map.remove(name);
+        // Rejoining closes the old connection even though it can't be told
+        let (new_events, _new_user_events) = mpsc::channel(1);
+        let _rejoined = users.rejoin_user(&joined.resume_token, new_events).await.unwrap();
+        assert!(joined.closed.await.is_ok());
     }
 }
@@ -0,0 +1,435 @@
+use super::api::{Choice, Media, MediaSource, Question, QuestionKind, QuizProblem, RoomSettings, ScoringSettings};
+
+use std::collections::HashSet;
+
+/// The most questions a quiz can have.
+const MAX_QUESTIONS: usize = 100;
+/// The longest a question can last, in seconds.
+const MAX_TIME: u16 = 600;
+/// The fewest choices (or ordering items) a question can have.
+const MIN_CHOICES: usize = 2;
+/// The most choices (or ordering items) a question can have.
+const MAX_CHOICES: usize = 10;
+/// The longest question text allowed.
+const MAX_QUESTION_LENGTH: usize = 500;
+/// The longest choice, item or accepted answer allowed.
+const MAX_CHOICE_LENGTH: usize = 200;
+/// The longest word cloud entry a host can allow.
+const MAX_WORD_CLOUD_LENGTH: usize = 100;
+/// The longest media url allowed.
+const MAX_URL_LENGTH: usize = 2048;
+/// The longest alt text allowed.
+const MAX_ALT_TEXT_LENGTH: usize = 500;
+/// The most teams a room can have.
+const MAX_TEAMS: usize = 10;
+/// The longest team name allowed.
+const MAX_TEAM_NAME_LENGTH: usize = 50;
+/// The longest lead-in before a question, in seconds.
+const MAX_LEAD_IN: u16 = 60;
+/// The most points a single answer can be worth.
+const MAX_POINTS: u32 = 100_000;
+/// The biggest streak bonus a room can have, per answer and in total.
+const MAX_STREAK_BONUS: u32 = 100_000;
+/// The longest players can take to reconnect, in seconds.
+const MAX_RECONNECT_GRACE: u16 = 600;
+
+/// Checks a quiz before a room is created for it.
+///
+/// Returns every problem found, not only the first.
+pub fn check_quiz(questions: &[Question]) -> Result<(), Vec<QuizProblem>> {
+    let mut problems = Problems::default();
+
+    if questions.is_empty() {
+        problems.add("questions", "the quiz has no questions");
+    }
+    if questions.len() > MAX_QUESTIONS {
+        problems.add("questions", format!("the quiz has more than {MAX_QUESTIONS} questions"));
+    }
+
+    for (i, question) in questions.iter().enumerate() {
+        problems.question = Some(i);
+        check_question(&mut problems, question);
+    }
+
+    if problems.list.is_empty() {
+        Ok(())
+    } else {
+        Err(problems.list)
+    }
+}
+
+/// Checks the settings a room is created with.
+pub fn check_settings(settings: &RoomSettings) -> Result<(), Vec<QuizProblem>> {
+    let mut problems = Problems::default();
+
+    if let Some(teams) = &settings.teams {
+        if teams.names.len() < 2 || teams.names.len() > MAX_TEAMS {
+            problems.add("settings.teams.names", format!("must have 2 to {MAX_TEAMS} teams"));
+        }
+        for (i, name) in teams.names.iter().enumerate() {
+            problems.text(format!("settings.teams.names[{i}]"), name, MAX_TEAM_NAME_LENGTH);
+        }
+        if teams.names.iter().collect::<HashSet<_>>().len() != teams.names.len() {
+            problems.add("settings.teams.names", "must not contain duplicates");
+        }
+    }
+
+    match settings.scoring {
+        ScoringSettings::TimeBased { max_points, min_points } => {
+            if max_points > MAX_POINTS {
+                problems.add("settings.scoring.maxPoints", format!("must be at most {MAX_POINTS}"));
+            }
+            if min_points > max_points {
+                problems.add("settings.scoring.minPoints", "must be at most maxPoints");
+            }
+        }
+        ScoringSettings::OrderBased { first_points } => {
+            if first_points > MAX_POINTS {
+                problems.add("settings.scoring.firstPoints", format!("must be at most {MAX_POINTS}"));
+            }
+        }
+        ScoringSettings::Flat { points } => {
+            if points > MAX_POINTS {
+                problems.add("settings.scoring.points", format!("must be at most {MAX_POINTS}"));
+            }
+        }
+        ScoringSettings::AccuracyOnly => (),
+    }
+
+    if settings.streak_bonus > MAX_STREAK_BONUS {
+        problems.add("settings.streakBonus", format!("must be at most {MAX_STREAK_BONUS}"));
+    }
+    if settings.max_streak_bonus > MAX_STREAK_BONUS {
+        problems.add("settings.maxStreakBonus", format!("must be at most {MAX_STREAK_BONUS}"));
+    }
+
+    if settings.lead_in > MAX_LEAD_IN {
+        problems.add("settings.leadIn", format!("must be at most {MAX_LEAD_IN} seconds"));
+    }
+    if settings.tick_interval == Some(0) {
+        problems.add("settings.tickInterval", "must be at least 1 second");
+    }
+    if settings.reconnect_grace > MAX_RECONNECT_GRACE {
+        problems.add("settings.reconnectGrace", format!("must be at most {MAX_RECONNECT_GRACE} seconds"));
+    }
+
+    if problems.list.is_empty() {
+        Ok(())
+    } else {
+        Err(problems.list)
+    }
+}
+
+/// Collects problems, remembering which question is being checked.
+#[derive(Default)]
+struct Problems {
+    list: Vec<QuizProblem>,
+    question: Option<usize>,
+}
+
+impl Problems {
+    fn add(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.list.push(QuizProblem {
+            question: self.question,
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Checks that some text is neither blank nor too long.
+    fn text(&mut self, field: impl Into<String>, text: &str, max_length: usize) {
+        if text.trim().is_empty() {
+            self.add(field, "must not be empty");
+        } else if text.chars().count() > max_length {
+            self.add(field, format!("must be at most {max_length} characters"));
+        }
+    }
+}
+
+fn check_question(problems: &mut Problems, question: &Question) {
+    problems.text("question", &question.question, MAX_QUESTION_LENGTH);
+
+    if question.time == 0 || question.time > MAX_TIME {
+        problems.add("time", format!("must be between 1 and {MAX_TIME} seconds"));
+    }
+
+    if let Some(media) = &question.media {
+        check_media(problems, "media", media);
+    }
+
+    match &question.kind {
+        QuestionKind::MultipleChoice { choices, answer } => {
+            check_choices(problems, choices);
+
+            if *answer >= choices.len() {
+                problems.add("answer", "must be the index of a choice");
+            }
+        }
+        QuestionKind::MultiSelect { choices, answers, .. } => {
+            check_choices(problems, choices);
+
+            if answers.iter().any(|&answer| answer >= choices.len()) {
+                problems.add("answers", "must all be indices of choices");
+            }
+            if answers.iter().collect::<HashSet<_>>().len() != answers.len() {
+                problems.add("answers", "must not contain duplicates");
+            }
+        }
+        QuestionKind::TypeAnswer { accepted, .. } => {
+            if accepted.is_empty() {
+                problems.add("accepted", "must have at least one answer");
+            }
+            for (j, answer) in accepted.iter().enumerate() {
+                problems.text(format!("accepted[{j}]"), answer, MAX_CHOICE_LENGTH);
+            }
+        }
+        QuestionKind::Slider { min, max, step, answer, tolerance } => {
+            if ![min, max, step, answer, tolerance].iter().all(|n| n.is_finite()) {
+                problems.add("slider", "every number must be finite");
+                return;
+            }
+
+            if min >= max {
+                problems.add("max", "must be greater than min");
+            }
+            if *step <= 0.0 || *step > max - min {
+                problems.add("step", "must be positive and fit between min and max");
+            }
+            if answer < min || answer > max {
+                problems.add("answer", "must be between min and max");
+            }
+            if *tolerance < 0.0 {
+                problems.add("tolerance", "must not be negative");
+            }
+        }
+        QuestionKind::Ordering { items, .. } => {
+            if items.len() < MIN_CHOICES || items.len() > MAX_CHOICES {
+                problems.add("items", format!("must have {MIN_CHOICES} to {MAX_CHOICES} items"));
+            }
+            for (j, item) in items.iter().enumerate() {
+                problems.text(format!("items[{j}]"), item, MAX_CHOICE_LENGTH);
+            }
+            // The correct order would be ambiguous
+            if items.iter().collect::<HashSet<_>>().len() != items.len() {
+                problems.add("items", "must not contain duplicates");
+            }
+        }
+        QuestionKind::Poll { choices } => check_choices(problems, choices),
+        QuestionKind::WordCloud { max_length } => {
+            if *max_length == 0 || *max_length > MAX_WORD_CLOUD_LENGTH {
+                problems.add(
+                    "maxLength",
+                    format!("must be between 1 and {MAX_WORD_CLOUD_LENGTH} characters"),
+                );
+            }
+        }
+    }
+}
+
+fn check_choices(problems: &mut Problems, choices: &[Choice]) {
+    if choices.len() < MIN_CHOICES || choices.len() > MAX_CHOICES {
+        problems.add("choices", format!("must have {MIN_CHOICES} to {MAX_CHOICES} choices"));
+    }
+
+    for (j, choice) in choices.iter().enumerate() {
+        problems.text(format!("choices[{j}].text"), &choice.text, MAX_CHOICE_LENGTH);
+
+        if let Some(media) = &choice.media {
+            check_media(problems, &format!("choices[{j}].media"), media);
+        }
+    }
+
+    // Players couldn't tell the choices apart
+    if choices.iter().map(|choice| &choice.text).collect::<HashSet<_>>().len() != choices.len() {
+        problems.add("choices", "must not contain duplicates");
+    }
+}
+
+/// Checks that a single piece of media is well formed.
+fn check_media(problems: &mut Problems, field: &str, media: &Media) {
+    let (url, start, end) = match &media.source {
+        MediaSource::Image { url } => (url, None, None),
+        MediaSource::Video { url, start, end } | MediaSource::Audio { url, start, end } => {
+            (url, *start, *end)
+        }
+    };
+
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        problems.add(format!("{field}.url"), "must start with http:// or https://");
+    } else if url.len() > MAX_URL_LENGTH {
+        problems.add(format!("{field}.url"), format!("must be at most {MAX_URL_LENGTH} characters"));
+    }
+
+    if let Some(start) = start {
+        if !start.is_finite() || start < 0.0 {
+            problems.add(format!("{field}.start"), "must be a positive number of seconds");
+        }
+    }
+    if let Some(end) = end {
+        if !end.is_finite() || end <= start.unwrap_or(0.0) {
+            problems.add(format!("{field}.end"), "must come after the start");
+        }
+    }
+
+    if let Some(alt_text) = &media.alt_text {
+        if alt_text.chars().count() > MAX_ALT_TEXT_LENGTH {
+            problems.add(
+                format!("{field}.altText"),
+                format!("must be at most {MAX_ALT_TEXT_LENGTH} characters"),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ws::api::{PointMultiplier, Question, QuestionKind, QuizProblem, RoomSettings, ScoringSettings, TeamSettings};
+
+    use super::{check_quiz, check_settings};
+
+    fn problem(question: Option<usize>, field: &str) -> (Option<usize>, String) {
+        (question, String::from(field))
+    }
+
+    fn fields(problems: Vec<QuizProblem>) -> Vec<(Option<usize>, String)> {
+        problems.into_iter().map(|p| (p.question, p.field)).collect()
+    }
+
+    #[test]
+    fn empty_quiz() {
+        let problems = check_quiz(&[]).unwrap_err();
+        assert_eq!(fields(problems), vec![problem(None, "questions")]);
+    }
+
+    #[test]
+    fn lists_every_problem() {
+        let good = Question {
+            question: String::from("Fish?"),
+            media: None,
+            kind: QuestionKind::MultipleChoice {
+                choices: vec!["foo".into(), "bar".into()],
+                answer: 0,
+            },
+            time: 30,
+            points: PointMultiplier::Standard,
+        };
+        let bad = Question {
+            question: "?".repeat(1000),
+            media: None,
+            kind: QuestionKind::MultipleChoice {
+                choices: vec!["foo".into(), " ".into()],
+                answer: 2,
+            },
+            time: 0,
+            points: PointMultiplier::Standard,
+        };
+
+        assert_eq!(check_quiz(std::slice::from_ref(&good)), Ok(()));
+
+        let problems = check_quiz(&[good, bad]).unwrap_err();
+        assert_eq!(
+            fields(problems),
+            vec![
+                problem(Some(1), "question"),
+                problem(Some(1), "time"),
+                problem(Some(1), "choices[1].text"),
+                problem(Some(1), "answer"),
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicates() {
+        let question = |kind| Question {
+            question: String::from("Fish?"),
+            media: None,
+            kind,
+            time: 30,
+            points: PointMultiplier::Standard,
+        };
+
+        let choices = question(QuestionKind::MultipleChoice {
+            choices: vec!["foo".into(), "bar".into(), "foo".into()],
+            answer: 0,
+        });
+        let problems = check_quiz(&[choices]).unwrap_err();
+        assert_eq!(fields(problems), vec![problem(Some(0), "choices")]);
+
+        let items = question(QuestionKind::Ordering {
+            items: vec![String::from("foo"), String::from("foo")],
+            scoring: Default::default(),
+        });
+        let problems = check_quiz(&[items]).unwrap_err();
+        assert_eq!(fields(problems), vec![problem(Some(0), "items")]);
+    }
+
+    #[test]
+    fn scoring_limits() {
+        let settings = |scoring| RoomSettings { scoring, ..RoomSettings::default() };
+
+        let scoring = ScoringSettings::TimeBased { max_points: 2000, min_points: 100 };
+        assert_eq!(check_settings(&settings(scoring)), Ok(()));
+
+        let scoring = ScoringSettings::TimeBased { max_points: 500_000_000, min_points: 600_000_000 };
+        let problems = check_settings(&settings(scoring)).unwrap_err();
+        assert_eq!(
+            fields(problems),
+            vec![
+                problem(None, "settings.scoring.maxPoints"),
+                problem(None, "settings.scoring.minPoints"),
+            ]
+        );
+
+        let scoring = ScoringSettings::OrderBased { first_points: 500_000_000 };
+        let problems = check_settings(&settings(scoring)).unwrap_err();
+        assert_eq!(fields(problems), vec![problem(None, "settings.scoring.firstPoints")]);
+
+        let scoring = ScoringSettings::Flat { points: 500_000_000 };
+        let problems = check_settings(&settings(scoring)).unwrap_err();
+        assert_eq!(fields(problems), vec![problem(None, "settings.scoring.points")]);
+    }
+
+    #[test]
+    fn streak_bonus_limits() {
+        let settings = RoomSettings {
+            streak_bonus: 3_000_000_000,
+            max_streak_bonus: 3_000_000_000,
+            ..RoomSettings::default()
+        };
+        let problems = check_settings(&settings).unwrap_err();
+        assert_eq!(
+            fields(problems),
+            vec![
+                problem(None, "settings.streakBonus"),
+                problem(None, "settings.maxStreakBonus"),
+            ]
+        );
+    }
+
+    #[test]
+    fn team_names() {
+        let settings = |names: &[&str]| RoomSettings {
+            teams: Some(TeamSettings {
+                names: names.iter().map(|&name| String::from(name)).collect(),
+                assignment: Default::default(),
+                scoring: Default::default(),
+            }),
+            ..RoomSettings::default()
+        };
+
+        assert_eq!(check_settings(&RoomSettings::default()), Ok(()));
+        assert_eq!(check_settings(&settings(&["Red", "Blue"])), Ok(()));
+
+        let problems = check_settings(&settings(&["Red"])).unwrap_err();
+        assert_eq!(fields(problems), vec![problem(None, "settings.teams.names")]);
+
+        let problems = check_settings(&settings(&["Red", "", "Red"])).unwrap_err();
+        assert_eq!(
+            fields(problems),
+            vec![
+                problem(None, "settings.teams.names[1]"),
+                problem(None, "settings.teams.names"),
+            ]
+        );
+    }
+}
@@ -31,16 +31,32 @@ export namespace auth {
   }
 }
 
+/** A choice as the server sends it. */
+export interface Choice {
+  text: string;
+  media?: unknown;
+}
+
+/** A multiple choice question as the server sends it to the host. */
 export interface rustServerQuestion {
   question: string;
-  choices: string[];
+  kind: "multipleChoice";
+  choices: Choice[];
   answer: number;
   time: number;
 }
 export namespace action {
+  /** A multiple choice question sent to the server, choices can be plain text. */
+  declare interface NewQuestion {
+    question: string;
+    choices: (string | Choice)[];
+    answer: number;
+    time: number;
+  }
+
   declare interface CreateRoom {
     type: "createRoom";
-    questions: rustServerQuestion[];
+    questions: NewQuestion[];
   }
   declare interface JoinRoom {
     type: "joinRoom";
@@ -50,6 +66,8 @@ export namespace action {
 
   declare interface Answer {
     type: "answer";
+    /** The `roundId` from the `roundBegin` being answered. */
+    roundId: number;
     choice: number;
   }
 
@@ -93,6 +111,11 @@ export namespace HostEvent {
   declare interface RoundBegin {
     type: "roundBegin";
     question: rustServerQuestion;
+    index: number;
+    total: number;
+    roundId: number;
+    serverTime: number;
+    deadline: number;
   }
 
   declare interface RoundEnd {
@@ -109,6 +132,7 @@ export namespace UserEvent {
   declare type event = Joined | JoinFailed | RoundBegin | RoundEnd | GameEnd;
   declare interface Joined {
     type: "joined";
+    resumeToken: string;
   }
 
   declare interface JoinFailed {
@@ -118,7 +142,12 @@ export namespace UserEvent {
 
   declare interface RoundBegin {
     type: "roundBegin";
-    choices: string[];
+    question: string;
+    kind: "multipleChoice";
+    choices: Choice[];
+    roundId: number;
+    serverTime: number;
+    deadline: number;
   }
 
   declare interface RoundEnd {
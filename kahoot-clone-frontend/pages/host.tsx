@@ -227,7 +227,7 @@ function QuestionDisplay({
                     className={`${qStyles.answer} ${qStyles.whiteText}`}
                     suppressContentEditableWarning
                   >
-                    {question.choices[0]}
+                    {question.choices[0].text}
                   </p>
                 </div>
                 {showAnswer && question.answer === 0 && (
@@ -249,7 +249,7 @@ function QuestionDisplay({
                   placeholder="Resposta 2"
                   suppressContentEditableWarning
                 >
-                  {question.choices[1]}
+                  {question.choices[1].text}
                 </p>
               </div>
               {showAnswer && question.answer === 1 && (
@@ -267,7 +267,7 @@ function QuestionDisplay({
               ${qStyles.whiteText}`}
                     suppressContentEditableWarning
                   >
-                    {question.choices[2]}
+                    {question.choices[2].text}
                   </p>
                 </div>
                 {showAnswer && question.answer === 2 && (
@@ -287,7 +287,7 @@ function QuestionDisplay({
                     className={`${qStyles.answer} ${qStyles.whiteText}`}
                     suppressContentEditableWarning
                   >
-                    {question.choices[3]}
+                    {question.choices[3].text}
                   </p>
                 </div>
                 {showAnswer && question.answer === 3 && (
@@ -587,9 +587,18 @@ function Host() {
             JSON.stringify({
               type: "createRoom",
               questions: results[1].questions.map((question) => {
-                const formattedQuestion = { ...question } as any;
-                formattedQuestion.answer = formattedQuestion.correctAnswer;
-                delete formattedQuestion.correctAnswer;
+                // The server rejects blank choices, so unused ones are left
+                // out and the answer index is moved to match
+                const choices = question.choices.filter((choice) => choice !== "");
+                const answer = question.choices
+                  .slice(0, question.correctAnswer)
+                  .filter((choice) => choice !== "").length;
+                const formattedQuestion: action.NewQuestion = {
+                  question: question.question,
+                  choices,
+                  answer,
+                  time: question.time,
+                };
                 return formattedQuestion;
               }),
             })
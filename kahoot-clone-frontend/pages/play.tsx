@@ -159,11 +159,11 @@ function StartScreen() {
 
 function ChooseAnswer({ data }) {
   const { socket } = useContext(PlayerContext);
-  const choices = (data as UserEvent.RoundBegin).choices;
+  const { choices, roundId } = data as UserEvent.RoundBegin;
   const [madeChoice, setMadeChoice] = useState(false);
   const onChoiceMade = (index) => () => {
     setMadeChoice(true);
-    const request: action.Answer = { type: "answer", choice: index };
+    const request: action.Answer = { type: "answer", roundId, choice: index };
     socket.send(JSON.stringify(request));
   };
   return (
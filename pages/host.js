@@ -21,7 +21,7 @@ var receiveMessage = (e) => {
 		questionDisplay.innerText = q.question;
 		for (choice of q.choices) {
 			const li = document.createElement("li");
-			li.innerText = choice;
+			li.innerText = choice.text;
 			choices.appendChild(li);
 		}
 	}
@@ -9,8 +9,15 @@ const display = document.getElementById("display");
 // username stuff
 const input = document.getElementById("username");
 //  Listen for messages
+// the round being answered, answers must say which round they're for
+let roundId = null;
+
 var receiveMessage = (e) => {
 	console.log("Message from server: ", event.data);
+	const data = JSON.parse(event.data);
+	if (data.type === "roundBegin") {
+		roundId = data.roundId;
+	}
 };
 
 socket.addEventListener("message", receiveMessage);
@@ -36,6 +43,7 @@ var sendAnswer = (e) => {
 	const answer = event.target.innerHTML;
 	let msg = {
 		type: "answer",
+		roundId: roundId,
 		choice: parseInt(answer) - 1,
 	};
 	msg = JSON.stringify(msg);